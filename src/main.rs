@@ -1,28 +1,126 @@
 use std::path::PathBuf;
 use std::process::Command;
 
-use iced::widget::{button, center, column, radio, slider, text};
+use iced::widget::{
+    button, checkbox, column, container, pick_list, radio, row, scrollable, slider, text,
+    text_input,
+};
 use iced::{Center, Element, Fill, Task};
-use rfd::FileDialog;
+use rfd::{FileDialog, MessageButtons, MessageDialog, MessageDialogResult, MessageLevel};
 
 mod proxy;
 
+const WATERMARK_PRESETS: [&str; 2] = ["PROXY", "PLAYTEST"];
+
 pub fn main() -> iced::Result {
-    iced::run("Proxy Creator", ProxyConfig::update, ProxyConfig::view)
+    iced::application("Proxy Creator", ProxyConfig::update, ProxyConfig::view)
+        .run_with(ProxyConfig::new)
 }
 
 #[derive(Default)]
 struct ProxyConfig {
-    selected_schema: bool,
+    selected_schema: proxy::PageLayout,
     padding_value: f64,
+    card_size_preset: proxy::CardSizePreset,
+    custom_card_size: proxy::CardSize,
+    image_quality: proxy::ImageQuality,
+    corner_fill: proxy::CornerFill,
+    border_extension: proxy::BorderExtension,
+    print_mode: proxy::PrintMode,
+    adjustments: proxy::ImageAdjustments,
+    adjustment_presets: proxy::AdjustmentPresets,
+    /// Printer name the adjustments are saved under
+    preset_name: String,
+    /// Downsized card image the adjustments are previewed on
+    preview_source: Option<image::DynamicImage>,
+    /// Preview before and after the adjustments
+    preview: Option<(iced::widget::image::Handle, iced::widget::image::Handle)>,
+    upscaling: proxy::Upscaling,
+    watermark_enabled: bool,
+    watermark: proxy::Watermark,
+    output: proxy::OutputSettings,
+    compression: proxy::Compression,
+    color: proxy::ColorSettings,
+    card_order: proxy::CardOrder,
+    basic_lands_last: bool,
+    meld_backs: bool,
+    art_series: bool,
+    oversized_planes: bool,
+    captions: bool,
+    cut_marks: bool,
+    cover_page: bool,
+    duplex_enabled: bool,
+    duplex: proxy::Duplex,
+    back_image: Option<PathBuf>,
+    printer_correction: proxy::PrinterCorrection,
     file_path: Option<PathBuf>,
+    image_dir: Option<PathBuf>,
+    /// Path and file size of the last created PDF
+    created_pdf: Option<String>,
 }
 
 #[derive(Debug, Clone)]
 enum Message {
-    SchemaChange(bool),
+    SchemaChange(proxy::PageLayout),
     PaddingChanged(f64),
+    CardSizePresetChanged(proxy::CardSizePreset),
+    CustomCardWidthChanged(f64),
+    CustomCardHeightChanged(f64),
+    ImageQualityChanged(proxy::ImageQuality),
+    CornerFillChanged(proxy::CornerFill),
+    BorderWidthChanged(f64),
+    BorderFitChanged(proxy::BorderFit),
+    PrintModeChanged(proxy::PrintMode),
+    BrightnessChanged(f64),
+    ContrastChanged(f64),
+    GammaChanged(f64),
+    SaturationChanged(f64),
+    ResetAdjustmentsButtonPressed,
+    PreviewImageSelectButtonPressed,
+    PresetSelected(String),
+    PresetNameChanged(String),
+    SavePresetButtonPressed,
+    DeletePresetButtonPressed,
+    UpscalingToggled(bool),
+    UpscalingDpiChanged(f64),
+    MinDpiChanged(f64),
+    WatermarkToggled(bool),
+    WatermarkTextChanged(String),
+    WatermarkPositionChanged(proxy::WatermarkPosition),
+    WatermarkOpacityChanged(f64),
+    WatermarkRotationChanged(f64),
+    OutputDirSelectButtonPressed,
+    FileNameTemplateChanged(String),
+    ConflictPolicyChanged(proxy::ConflictPolicy),
+    OutputFormatChanged(proxy::OutputFormat),
+    ImageDpiChanged(f64),
+    SaveAsButtonPressed,
+    CompressionFormatChanged(proxy::CompressionFormat),
+    JpegQualityChanged(u8),
+    DownsampleToggled(bool),
+    TargetDpiChanged(f64),
+    ColorOutputChanged(proxy::ColorOutput),
+    CmykProfileSelectButtonPressed,
+    CardOrderChanged(proxy::CardOrder),
+    BasicLandsLastToggled(bool),
+    MeldBacksToggled(bool),
+    ArtSeriesToggled(bool),
+    OversizedPlanesToggled(bool),
+    CaptionsToggled(bool),
+    CutMarksToggled(bool),
+    CoverPageToggled(bool),
+    DuplexToggled(bool),
+    DuplexFlipChanged(proxy::DuplexFlip),
+    DuplexOffsetXChanged(f64),
+    DuplexOffsetYChanged(f64),
+    BackImageSelectButtonPressed,
+    ScaleXChanged(f64),
+    ScaleYChanged(f64),
+    OffsetXChanged(f64),
+    OffsetYChanged(f64),
+    CalibrationPageButtonPressed,
     FileSelectButtonPressed,
+    ImageDirSelectButtonPressed,
     StartButtonPressed,
     ProxyPdfFileCreated(Result<PathBuf, proxy::PdfPathNotCreated>),
 }
@@ -39,21 +137,201 @@ fn open_file(path: PathBuf) {
 
     cmd.arg(path);
 
-    // This will block, but if you don't mind, it's fine for quick tasks
-    cmd.spawn().unwrap();
+    // The viewer keeps running on its own, waiting for it would freeze the window
+    if let Err(e) = cmd.spawn() {
+        eprintln!("Could not open the PDF file: {}", e);
+    }
+}
+
+fn preview_handle(image: &image::DynamicImage) -> iced::widget::image::Handle {
+    let rgba = image.to_rgba8();
+    iced::widget::image::Handle::from_rgba(rgba.width(), rgba.height(), rgba.into_raw())
 }
 
 impl ProxyConfig {
+    fn new() -> (Self, Task<Message>) {
+        let mut config = ProxyConfig::default();
+        if let Some(presets_path) = proxy::AdjustmentPresets::default_path() {
+            match proxy::AdjustmentPresets::load(&presets_path) {
+                Ok(presets) => config.adjustment_presets = presets,
+                Err(e) => eprintln!("Could not load the adjustment presets: {e:#}"),
+            }
+        }
+        config.set_preview_image(None);
+        (config, Task::none())
+    }
+
+    fn set_preview_image(&mut self, image_path: Option<&std::path::Path>) {
+        match proxy::load_preview_image(image_path) {
+            Ok(image) => self.preview_source = Some(image),
+            Err(e) => eprintln!("Could not load the preview image: {e:#}"),
+        }
+        self.update_preview();
+    }
+
+    fn update_preview(&mut self) {
+        self.preview = self.preview_source.as_ref().map(|image| {
+            let adjusted = proxy::adjust_image(image.clone(), self.adjustments);
+            (preview_handle(image), preview_handle(&adjusted))
+        });
+    }
+
+    fn save_presets(&self) {
+        let Some(presets_path) = proxy::AdjustmentPresets::default_path() else {
+            eprintln!("Could not save the adjustment presets: no config folder found");
+            return;
+        };
+        if let Err(e) = self.adjustment_presets.save(&presets_path) {
+            eprintln!("Could not save the adjustment presets: {e:#}");
+        }
+    }
+
     fn update(&mut self, message: Message) -> Task<Message> {
         match message {
             Message::SchemaChange(schema) => {
                 self.selected_schema = schema;
+                self.clamp_padding();
                 Task::none()
             }
             Message::PaddingChanged(padding) => {
                 self.padding_value = padding;
                 Task::none()
             }
+            Message::CardSizePresetChanged(preset) => {
+                self.card_size_preset = preset;
+                self.clamp_padding();
+                Task::none()
+            }
+            Message::CustomCardWidthChanged(width_mm) => {
+                self.custom_card_size.width_mm = width_mm;
+                self.clamp_padding();
+                Task::none()
+            }
+            Message::CustomCardHeightChanged(height_mm) => {
+                self.custom_card_size.height_mm = height_mm;
+                self.clamp_padding();
+                Task::none()
+            }
+            Message::ImageQualityChanged(image_quality) => {
+                self.image_quality = image_quality;
+                Task::none()
+            }
+            Message::CornerFillChanged(corner_fill) => {
+                self.corner_fill = corner_fill;
+                Task::none()
+            }
+            Message::BorderWidthChanged(width_mm) => {
+                self.border_extension.width_mm = width_mm;
+                Task::none()
+            }
+            Message::BorderFitChanged(fit) => {
+                self.border_extension.fit = fit;
+                Task::none()
+            }
+            Message::PrintModeChanged(print_mode) => {
+                self.print_mode = print_mode;
+                Task::none()
+            }
+            Message::BrightnessChanged(brightness) => {
+                self.adjustments.brightness = brightness;
+                self.update_preview();
+                Task::none()
+            }
+            Message::ContrastChanged(contrast) => {
+                self.adjustments.contrast = contrast;
+                self.update_preview();
+                Task::none()
+            }
+            Message::GammaChanged(gamma) => {
+                self.adjustments.gamma = gamma;
+                self.update_preview();
+                Task::none()
+            }
+            Message::SaturationChanged(saturation) => {
+                self.adjustments.saturation = saturation;
+                self.update_preview();
+                Task::none()
+            }
+            Message::ResetAdjustmentsButtonPressed => {
+                self.adjustments = proxy::ImageAdjustments::default();
+                self.update_preview();
+                Task::none()
+            }
+            Message::PreviewImageSelectButtonPressed => {
+                if let Some(image_path) = FileDialog::new()
+                    .add_filter("Image", &["png", "jpg", "jpeg", "webp"])
+                    .pick_file()
+                {
+                    self.set_preview_image(Some(&image_path));
+                }
+                Task::none()
+            }
+            Message::PresetSelected(name) => {
+                if let Some(adjustments) = self.adjustment_presets.presets.get(&name) {
+                    self.adjustments = *adjustments;
+                    self.update_preview();
+                }
+                self.preset_name = name;
+                Task::none()
+            }
+            Message::PresetNameChanged(name) => {
+                self.preset_name = name;
+                Task::none()
+            }
+            Message::SavePresetButtonPressed => {
+                let name = self.preset_name.trim().to_string();
+                if !name.is_empty() {
+                    self.adjustment_presets
+                        .presets
+                        .insert(name.clone(), self.adjustments);
+                    self.preset_name = name;
+                    self.save_presets();
+                }
+                Task::none()
+            }
+            Message::DeletePresetButtonPressed => {
+                if self
+                    .adjustment_presets
+                    .presets
+                    .remove(&self.preset_name)
+                    .is_some()
+                {
+                    self.save_presets();
+                }
+                Task::none()
+            }
+            Message::UpscalingToggled(enabled) => {
+                self.upscaling.enabled = enabled;
+                Task::none()
+            }
+            Message::UpscalingDpiChanged(dpi) => {
+                self.upscaling.target_dpi = dpi;
+                Task::none()
+            }
+            Message::MinDpiChanged(dpi) => {
+                self.upscaling.min_dpi = dpi;
+                Task::none()
+            }
+            Message::WatermarkToggled(enabled) => {
+                self.watermark_enabled = enabled;
+                Task::none()
+            }
+            Message::WatermarkTextChanged(watermark_text) => {
+                self.watermark.text = watermark_text;
+                Task::none()
+            }
+            Message::WatermarkPositionChanged(position) => {
+                self.watermark.position = position;
+                Task::none()
+            }
+            Message::WatermarkOpacityChanged(opacity) => {
+                self.watermark.opacity = opacity;
+                Task::none()
+            }
+            Message::WatermarkRotationChanged(rotation) => {
+                self.watermark.rotation = rotation;
+                Task::none()
+            }
             Message::FileSelectButtonPressed => {
                 // Block until user selects file
                 let selected_file_path = FileDialog::new()
@@ -64,19 +342,220 @@ impl ProxyConfig {
                 self.file_path = selected_file_path;
                 Task::none()
             }
-            Message::StartButtonPressed => Task::perform(
-                proxy::main(
-                    self.file_path.clone(),
-                    self.selected_schema,
-                    self.padding_value,
-                ),
-                Message::ProxyPdfFileCreated,
-            ),
+            Message::ImageDirSelectButtonPressed => {
+                // Keep the previous folder if the dialog is cancelled
+                if let Some(image_dir) = FileDialog::new().pick_folder() {
+                    self.image_dir = Some(image_dir);
+                }
+                Task::none()
+            }
+            Message::OutputDirSelectButtonPressed => {
+                if let Some(dir) = FileDialog::new().pick_folder() {
+                    self.output.dir = Some(dir);
+                }
+                Task::none()
+            }
+            Message::FileNameTemplateChanged(template) => {
+                self.output.file_name_template = template;
+                Task::none()
+            }
+            Message::ConflictPolicyChanged(conflict_policy) => {
+                self.output.conflict_policy = conflict_policy;
+                Task::none()
+            }
+            Message::OutputFormatChanged(format) => {
+                self.output.format = format;
+                Task::none()
+            }
+            Message::ImageDpiChanged(dpi) => {
+                self.output.image_dpi = dpi;
+                Task::none()
+            }
+            Message::SaveAsButtonPressed => {
+                let format = self.output.format;
+                let mut dialog =
+                    FileDialog::new().add_filter(format.to_string(), &[format.extension()]);
+                if let Some(file_path) = &self.file_path {
+                    let target = self
+                        .output
+                        .target_path(file_path, self.selected_schema.name());
+                    if let Some(dir) = target.parent() {
+                        dialog = dialog.set_directory(dir);
+                    }
+                    if let Some(file_name) = target.file_name() {
+                        dialog = dialog.set_file_name(file_name.to_string_lossy());
+                    }
+                }
+
+                if let Some(output_path) = dialog.save_file() {
+                    self.output.dir = output_path.parent().map(PathBuf::from);
+                    self.output.file_name_template = output_path
+                        .file_name()
+                        .map(|name| name.to_string_lossy().into_owned())
+                        .unwrap_or_default();
+                    // the save dialog already asks before replacing a file
+                    self.output.conflict_policy = proxy::ConflictPolicy::Overwrite;
+                }
+                Task::none()
+            }
+            Message::CompressionFormatChanged(format) => {
+                self.compression.format = format;
+                Task::none()
+            }
+            Message::JpegQualityChanged(quality) => {
+                self.compression.jpeg_quality = quality;
+                Task::none()
+            }
+            Message::DownsampleToggled(downsample) => {
+                self.compression.downsample = downsample;
+                Task::none()
+            }
+            Message::TargetDpiChanged(dpi) => {
+                self.compression.target_dpi = dpi;
+                Task::none()
+            }
+            Message::ColorOutputChanged(output) => {
+                self.color.output = output;
+                Task::none()
+            }
+            Message::CmykProfileSelectButtonPressed => {
+                if let Some(profile) = FileDialog::new()
+                    .add_filter("ICC profile", &["icc", "icm"])
+                    .pick_file()
+                {
+                    self.color.cmyk_profile = Some(profile);
+                }
+                Task::none()
+            }
+            Message::CardOrderChanged(card_order) => {
+                self.card_order = card_order;
+                Task::none()
+            }
+            Message::BasicLandsLastToggled(basic_lands_last) => {
+                self.basic_lands_last = basic_lands_last;
+                Task::none()
+            }
+            Message::MeldBacksToggled(meld_backs) => {
+                self.meld_backs = meld_backs;
+                Task::none()
+            }
+            Message::ArtSeriesToggled(art_series) => {
+                self.art_series = art_series;
+                Task::none()
+            }
+            Message::OversizedPlanesToggled(oversized_planes) => {
+                self.oversized_planes = oversized_planes;
+                Task::none()
+            }
+            Message::CaptionsToggled(captions) => {
+                self.captions = captions;
+                Task::none()
+            }
+            Message::CutMarksToggled(cut_marks) => {
+                self.cut_marks = cut_marks;
+                Task::none()
+            }
+            Message::CoverPageToggled(cover_page) => {
+                self.cover_page = cover_page;
+                Task::none()
+            }
+            Message::DuplexToggled(enabled) => {
+                self.duplex_enabled = enabled;
+                Task::none()
+            }
+            Message::DuplexFlipChanged(flip) => {
+                self.duplex.flip = flip;
+                Task::none()
+            }
+            Message::DuplexOffsetXChanged(offset) => {
+                self.duplex.offset_x_mm = offset;
+                Task::none()
+            }
+            Message::DuplexOffsetYChanged(offset) => {
+                self.duplex.offset_y_mm = offset;
+                Task::none()
+            }
+            Message::BackImageSelectButtonPressed => {
+                if let Some(back_image) = FileDialog::new()
+                    .add_filter("Image", &["png", "jpg", "jpeg", "webp"])
+                    .pick_file()
+                {
+                    self.back_image = Some(back_image);
+                }
+                Task::none()
+            }
+            Message::ScaleXChanged(percent) => {
+                self.printer_correction.scale_x = percent / 100.0;
+                Task::none()
+            }
+            Message::ScaleYChanged(percent) => {
+                self.printer_correction.scale_y = percent / 100.0;
+                Task::none()
+            }
+            Message::OffsetXChanged(offset) => {
+                self.printer_correction.offset_x_mm = offset;
+                Task::none()
+            }
+            Message::OffsetYChanged(offset) => {
+                self.printer_correction.offset_y_mm = offset;
+                Task::none()
+            }
+            Message::CalibrationPageButtonPressed => {
+                let pdf_path = FileDialog::new()
+                    .add_filter("PDF", &["pdf"])
+                    .set_file_name("calibration.pdf")
+                    .save_file();
+                if let Some(pdf_path) = pdf_path {
+                    match proxy::create_calibration_page(&pdf_path, &self.proxy_options()) {
+                        Ok(pdf_path) => open_file(pdf_path),
+                        Err(e) => eprintln!("Could not create the calibration page: {e:#}"),
+                    }
+                }
+                Task::none()
+            }
+            Message::StartButtonPressed => {
+                let mut options = self.proxy_options();
+
+                if let Some(file_path) = &self.file_path {
+                    let output_path = options.output.target_path(file_path, options.layout.name());
+                    let first_file = options.output.first_file(&output_path);
+                    if options.output.conflict_policy == proxy::ConflictPolicy::Ask
+                        && first_file.exists()
+                    {
+                        let answer = MessageDialog::new()
+                            .set_level(MessageLevel::Warning)
+                            .set_title("File already exists")
+                            .set_description(format!(
+                                "'{}' already exists. Do you want to replace it?",
+                                first_file.display()
+                            ))
+                            .set_buttons(MessageButtons::YesNo)
+                            .show();
+                        if answer != MessageDialogResult::Yes {
+                            return Task::none();
+                        }
+                        options.output.conflict_policy = proxy::ConflictPolicy::Overwrite;
+                    }
+                }
+
+                Task::perform(
+                    proxy::main(self.file_path.clone(), options),
+                    Message::ProxyPdfFileCreated,
+                )
+            }
 
             Message::ProxyPdfFileCreated(pdf_path_res) => {
                 if let Ok(pdf_path_res) = pdf_path_res {
+                    self.created_pdf = std::fs::metadata(&pdf_path_res).ok().map(|metadata| {
+                        format!(
+                            "Created {} ({})",
+                            pdf_path_res.display(),
+                            proxy::format_file_size(metadata.len())
+                        )
+                    });
                     open_file(pdf_path_res);
                 } else {
+                    self.created_pdf = None;
                     eprintln!("PDF creation failed, no file path.");
                 }
                 Task::none()
@@ -84,7 +563,61 @@ impl ProxyConfig {
         }
     }
 
-    fn view(&self) -> Element<Message> {
+    fn card_size(&self) -> proxy::CardSize {
+        self.card_size_preset
+            .size()
+            .unwrap_or(self.custom_card_size)
+    }
+
+    fn grid_layout(&self, padding: f64) -> proxy::GridLayout {
+        match self.selected_schema {
+            proxy::PageLayout::Gutterfold => {
+                proxy::GridLayout::gutterfold(self.card_size(), padding)
+            }
+            _ => proxy::GridLayout::fit(self.card_size(), padding),
+        }
+    }
+
+    // Largest padding that still fits the most cards of the selected size on the page
+    fn max_padding(&self) -> f64 {
+        self.grid_layout(0.0).max_padding()
+    }
+
+    fn clamp_padding(&mut self) {
+        self.padding_value = self.padding_value.min(self.max_padding());
+    }
+
+    fn proxy_options(&self) -> proxy::ProxyOptions {
+        proxy::ProxyOptions {
+            layout: self.selected_schema,
+            padding_length: self.padding_value,
+            card_size: self.card_size(),
+            image_quality: self.image_quality,
+            corner_fill: self.corner_fill,
+            border_extension: self.border_extension,
+            print_mode: self.print_mode,
+            adjustments: self.adjustments,
+            upscaling: self.upscaling,
+            watermark: self.watermark_enabled.then(|| self.watermark.clone()),
+            output: self.output.clone(),
+            compression: self.compression,
+            color: self.color.clone(),
+            card_order: self.card_order,
+            basic_lands_last: self.basic_lands_last,
+            meld_backs: self.meld_backs,
+            art_series: self.art_series,
+            oversized_planes: self.oversized_planes,
+            captions: self.captions,
+            cut_marks: self.cut_marks,
+            cover_page: self.cover_page,
+            duplex: self.duplex_enabled.then_some(self.duplex),
+            back_image: self.back_image.clone(),
+            printer_correction: self.printer_correction,
+            image_dir: self.image_dir.clone(),
+        }
+    }
+
+    fn view(&self) -> Element<'_, Message> {
         let file_button = column![
             button("Select .txt file").on_press(Message::FileSelectButtonPressed),
             text(
@@ -97,30 +630,111 @@ impl ProxyConfig {
         .width(Fill)
         .align_x(Center);
 
-        let one_by_one = radio(
-            "One card per page",
-            false,
-            Some(self.selected_schema),
-            Message::SchemaChange,
-        );
-
-        let matrix = radio(
-            "3x3 card matrix",
-            true,
-            Some(self.selected_schema),
-            Message::SchemaChange,
-        );
-
-        let choose_schema = column![text("Schema:"), one_by_one, matrix]
+        let image_dir_button = column![
+            button("Select custom image folder").on_press(Message::ImageDirSelectButtonPressed),
+            text(
+                self.image_dir
+                    .as_ref()
+                    .map(|p| format!("Custom images: {}", p.display()))
+                    .unwrap_or("No custom image folder".to_string())
+            )
+        ]
+        .width(Fill)
+        .align_x(Center);
+
+        let layouts = proxy::PageLayout::ALL.map(|layout| {
+            radio(
+                layout.to_string(),
+                layout,
+                Some(self.selected_schema),
+                Message::SchemaChange,
+            )
+            .into()
+        });
+
+        let captions = checkbox("Card name below each card", self.captions)
+            .on_toggle(Message::CaptionsToggled);
+
+        let cover_page = checkbox("Cover page with checklist", self.cover_page)
+            .on_toggle(Message::CoverPageToggled);
+
+        let cut_marks = checkbox("Cut marks", self.cut_marks).on_toggle(Message::CutMarksToggled);
+
+        let choose_schema = column![text("Schema:")]
+            .extend(layouts)
+            .push(captions)
+            .push(cut_marks)
+            .push(cover_page)
             .spacing(10)
             .width(Fill)
             .align_x(Center);
 
-        let padding_slider = if self.selected_schema {
+        let card_order = column![
+            text("Card order:"),
+            pick_list(
+                proxy::CardOrder::ALL,
+                Some(self.card_order),
+                Message::CardOrderChanged
+            ),
+            checkbox("Basic lands last", self.basic_lands_last)
+                .on_toggle(Message::BasicLandsLastToggled),
+            checkbox("Melded card on the back of meld cards", self.meld_backs)
+                .on_toggle(Message::MeldBacksToggled),
+            checkbox("Include art series cards", self.art_series)
+                .on_toggle(Message::ArtSeriesToggled),
+            checkbox("Oversized planes and schemes", self.oversized_planes)
+                .on_toggle(Message::OversizedPlanesToggled)
+        ]
+        .spacing(10)
+        .width(Fill)
+        .align_x(Center);
+
+        let card_size = self.card_size();
+        let custom_size = if self.card_size_preset == proxy::CardSizePreset::Custom {
+            column![
+                text(format!("Width {} mm", card_size.width_mm)),
+                slider(
+                    20.0..=200.0,
+                    card_size.width_mm,
+                    Message::CustomCardWidthChanged
+                )
+                .step(0.5),
+                text(format!("Height {} mm", card_size.height_mm)),
+                slider(
+                    20.0..=287.0,
+                    card_size.height_mm,
+                    Message::CustomCardHeightChanged
+                )
+                .step(0.5)
+            ]
+        } else {
+            column![]
+        };
+        let card_size_settings = column![
+            text("Card size:"),
+            pick_list(
+                proxy::CardSizePreset::ALL,
+                Some(self.card_size_preset),
+                Message::CardSizePresetChanged
+            ),
+            custom_size.spacing(10).width(Fill).align_x(Center)
+        ]
+        .spacing(10)
+        .width(Fill)
+        .align_x(Center);
+
+        let padding_slider = if self.selected_schema != proxy::PageLayout::Single {
+            let layout = self.grid_layout(self.padding_value);
             column![
                 text("Padding"),
-                slider(0.0..=10.5, self.padding_value, Message::PaddingChanged),
-                text(format!("{} mm", self.padding_value))
+                slider(
+                    0.0..=self.max_padding(),
+                    self.padding_value,
+                    Message::PaddingChanged
+                )
+                .step(0.5),
+                text(format!("{} mm", self.padding_value)),
+                text(format!("{} x {} cards per page", layout.cols, layout.rows))
             ]
             .width(Fill)
             .align_x(Center)
@@ -128,34 +742,433 @@ impl ProxyConfig {
             column![]
         };
 
+        let image_settings = column![
+            text("Image quality:"),
+            pick_list(
+                proxy::ImageQuality::ALL,
+                Some(self.image_quality),
+                Message::ImageQualityChanged
+            ),
+            text("Corner fill:"),
+            pick_list(
+                proxy::CornerFill::ALL,
+                Some(self.corner_fill),
+                Message::CornerFillChanged
+            ),
+            text("Print mode:"),
+            pick_list(
+                proxy::PrintMode::ALL,
+                Some(self.print_mode),
+                Message::PrintModeChanged
+            )
+        ]
+        .spacing(10)
+        .width(Fill)
+        .align_x(Center);
+
+        let border_extension = column![
+            text("Border extension"),
+            slider(
+                0.0..=3.0,
+                self.border_extension.width_mm,
+                Message::BorderWidthChanged
+            )
+            .step(0.25),
+            text(format!("{} mm", self.border_extension.width_mm)),
+            radio(
+                "Shrink card to fit",
+                proxy::BorderFit::ShrinkCard,
+                Some(self.border_extension.fit),
+                Message::BorderFitChanged,
+            ),
+            radio(
+                "Overflow card slot",
+                proxy::BorderFit::Overflow,
+                Some(self.border_extension.fit),
+                Message::BorderFitChanged,
+            )
+        ]
+        .spacing(10)
+        .width(Fill)
+        .align_x(Center);
+
+        let adjustments = self.adjustments;
+        let preset_names: Vec<String> = self.adjustment_presets.presets.keys().cloned().collect();
+        let has_preset = self
+            .adjustment_presets
+            .presets
+            .contains_key(&self.preset_name);
+        let adjustment_settings = column![
+            text("Image adjustments:"),
+            text(format!(
+                "Brightness {:+.0} %",
+                adjustments.brightness * 100.0
+            )),
+            slider(
+                -0.3..=0.3,
+                adjustments.brightness,
+                Message::BrightnessChanged
+            )
+            .step(0.01),
+            text(format!("Contrast {:.0} %", adjustments.contrast * 100.0)),
+            slider(0.5..=1.5, adjustments.contrast, Message::ContrastChanged).step(0.05),
+            text(format!("Gamma {:.2}", adjustments.gamma)),
+            slider(0.5..=2.5, adjustments.gamma, Message::GammaChanged).step(0.05),
+            text(format!(
+                "Saturation {:.0} %",
+                adjustments.saturation * 100.0
+            )),
+            slider(
+                0.0..=2.0,
+                adjustments.saturation,
+                Message::SaturationChanged
+            )
+            .step(0.05),
+            row![
+                button("Reset").on_press(Message::ResetAdjustmentsButtonPressed),
+                button("Select preview image").on_press(Message::PreviewImageSelectButtonPressed)
+            ]
+            .spacing(10)
+        ]
+        .push_maybe(self.preview.as_ref().map(|(before, after)| {
+            row![
+                column![text("Before"), iced::widget::image(before.clone())].align_x(Center),
+                column![text("After"), iced::widget::image(after.clone())].align_x(Center)
+            ]
+            .spacing(20)
+        }))
+        .push(text("Printer preset:"))
+        .push(
+            pick_list(
+                preset_names,
+                has_preset.then(|| self.preset_name.clone()),
+                Message::PresetSelected,
+            )
+            .placeholder("No preset"),
+        )
+        .push(
+            row![
+                text_input("Printer name", &self.preset_name).on_input(Message::PresetNameChanged),
+                button("Save preset").on_press_maybe(
+                    (!self.preset_name.trim().is_empty())
+                        .then_some(Message::SavePresetButtonPressed)
+                ),
+                button("Delete")
+                    .on_press_maybe(has_preset.then_some(Message::DeletePresetButtonPressed))
+            ]
+            .spacing(10),
+        )
+        .spacing(10)
+        .width(Fill)
+        .align_x(Center);
+
+        let upscaling = self.upscaling;
+        let upscaling_settings =
+            column![checkbox("Upscale low resolution images", upscaling.enabled)
+                .on_toggle(Message::UpscalingToggled)]
+            .push_maybe(upscaling.enabled.then(|| {
+                column![
+                    text(format!("Upscale to {} DPI", upscaling.target_dpi)),
+                    slider(
+                        200.0..=600.0,
+                        upscaling.target_dpi,
+                        Message::UpscalingDpiChanged
+                    )
+                    .step(50.0)
+                ]
+                .spacing(10)
+                .align_x(Center)
+            }))
+            .push(text(format!("Warn below {} DPI", upscaling.min_dpi)))
+            .push(slider(100.0..=300.0, upscaling.min_dpi, Message::MinDpiChanged).step(25.0))
+            .spacing(10)
+            .width(Fill)
+            .align_x(Center);
+
+        let compression = self.compression;
+        let mut compression_settings = column![
+            text("Compression:"),
+            pick_list(
+                proxy::CompressionFormat::ALL,
+                Some(compression.format),
+                Message::CompressionFormatChanged
+            )
+        ];
+        if compression.format == proxy::CompressionFormat::Jpeg {
+            compression_settings = compression_settings.extend([
+                text(format!("JPEG quality {}", compression.jpeg_quality)).into(),
+                slider(
+                    30..=100,
+                    compression.jpeg_quality,
+                    Message::JpegQualityChanged,
+                )
+                .into(),
+            ]);
+        }
+        compression_settings = compression_settings
+            .push(
+                checkbox("Downsample large images", compression.downsample)
+                    .on_toggle(Message::DownsampleToggled),
+            )
+            .push_maybe(compression.downsample.then(|| {
+                column![
+                    text(format!("Target {} DPI", compression.target_dpi)),
+                    slider(
+                        150.0..=600.0,
+                        compression.target_dpi,
+                        Message::TargetDpiChanged
+                    )
+                    .step(50.0)
+                ]
+                .spacing(10)
+                .align_x(Center)
+            }));
+        let compression_settings = compression_settings.spacing(10).width(Fill).align_x(Center);
+
+        let color_settings = column![
+            text("Color space:"),
+            pick_list(
+                proxy::ColorOutput::ALL,
+                Some(self.color.output),
+                Message::ColorOutputChanged
+            )
+        ]
+        .push_maybe((self.color.output == proxy::ColorOutput::Cmyk).then(|| {
+            column![
+                button("Select CMYK ICC profile").on_press(Message::CmykProfileSelectButtonPressed),
+                text(
+                    self.color
+                        .cmyk_profile
+                        .as_ref()
+                        .map(|p| format!("Profile: {}", p.display()))
+                        .unwrap_or("No profile selected".to_string())
+                )
+            ]
+            .spacing(10)
+            .align_x(Center)
+        }))
+        .spacing(10)
+        .width(Fill)
+        .align_x(Center);
+
+        let watermark = if self.watermark_enabled {
+            column![
+                checkbox("Watermark", self.watermark_enabled).on_toggle(Message::WatermarkToggled),
+                pick_list(
+                    WATERMARK_PRESETS,
+                    WATERMARK_PRESETS
+                        .into_iter()
+                        .find(|preset| *preset == self.watermark.text),
+                    |preset| Message::WatermarkTextChanged(preset.to_string())
+                )
+                .placeholder("Custom"),
+                text_input("Watermark text", &self.watermark.text)
+                    .on_input(Message::WatermarkTextChanged),
+                pick_list(
+                    proxy::WatermarkPosition::ALL,
+                    Some(self.watermark.position),
+                    Message::WatermarkPositionChanged
+                ),
+                text(format!("Opacity {:.0} %", self.watermark.opacity * 100.0)),
+                slider(
+                    0.1..=1.0,
+                    self.watermark.opacity,
+                    Message::WatermarkOpacityChanged
+                )
+                .step(0.05),
+                text(format!("Rotation {}°", self.watermark.rotation)),
+                slider(
+                    -90.0..=90.0,
+                    self.watermark.rotation,
+                    Message::WatermarkRotationChanged
+                )
+                .step(5.0)
+            ]
+        } else {
+            column![
+                checkbox("Watermark", self.watermark_enabled).on_toggle(Message::WatermarkToggled)
+            ]
+        }
+        .spacing(10)
+        .width(Fill)
+        .align_x(Center);
+
+        let duplex = if self.duplex_enabled {
+            column![
+                checkbox("Duplex back pages", self.duplex_enabled)
+                    .on_toggle(Message::DuplexToggled),
+                pick_list(
+                    proxy::DuplexFlip::ALL,
+                    Some(self.duplex.flip),
+                    Message::DuplexFlipChanged
+                ),
+                text(format!(
+                    "Back page offset X {:.1} mm",
+                    self.duplex.offset_x_mm
+                )),
+                slider(
+                    -5.0..=5.0,
+                    self.duplex.offset_x_mm,
+                    Message::DuplexOffsetXChanged
+                )
+                .step(0.1),
+                text(format!(
+                    "Back page offset Y {:.1} mm",
+                    self.duplex.offset_y_mm
+                )),
+                slider(
+                    -5.0..=5.0,
+                    self.duplex.offset_y_mm,
+                    Message::DuplexOffsetYChanged
+                )
+                .step(0.1)
+            ]
+        } else {
+            column![checkbox("Duplex back pages", self.duplex_enabled)
+                .on_toggle(Message::DuplexToggled)]
+        }
+        .spacing(10)
+        .width(Fill)
+        .align_x(Center);
+
+        let card_back = column![
+            button("Select card back image").on_press(Message::BackImageSelectButtonPressed),
+            text(
+                self.back_image
+                    .as_ref()
+                    .map(|p| format!("Card back: {}", p.display()))
+                    .unwrap_or("Card back: Magic card back".to_string())
+            )
+        ]
+        .spacing(10)
+        .width(Fill)
+        .align_x(Center);
+
+        let correction = self.printer_correction;
+        let calibration = column![
+            text("Printer calibration:"),
+            button("Create calibration page").on_press(Message::CalibrationPageButtonPressed),
+            text(format!("Scale X {:.1} %", correction.scale_x * 100.0)),
+            slider(
+                95.0..=105.0,
+                correction.scale_x * 100.0,
+                Message::ScaleXChanged
+            )
+            .step(0.1),
+            text(format!("Scale Y {:.1} %", correction.scale_y * 100.0)),
+            slider(
+                95.0..=105.0,
+                correction.scale_y * 100.0,
+                Message::ScaleYChanged
+            )
+            .step(0.1),
+            text(format!("Offset X {:.1} mm", correction.offset_x_mm)),
+            slider(-5.0..=5.0, correction.offset_x_mm, Message::OffsetXChanged).step(0.1),
+            text(format!("Offset Y {:.1} mm", correction.offset_y_mm)),
+            slider(-5.0..=5.0, correction.offset_y_mm, Message::OffsetYChanged).step(0.1)
+        ]
+        .spacing(10)
+        .width(Fill)
+        .align_x(Center);
+
+        let output =
+            column![
+                text("Output:"),
+                button("Select output folder").on_press(Message::OutputDirSelectButtonPressed),
+                text(
+                    self.output
+                        .dir
+                        .as_ref()
+                        .map(|p| format!("Output folder: {}", p.display()))
+                        .unwrap_or("Output folder: next to the decklist".to_string())
+                ),
+                text_input(
+                    "{deck}-{layout}-{date}.pdf",
+                    &self.output.file_name_template
+                )
+                .on_input(Message::FileNameTemplateChanged),
+                pick_list(
+                    proxy::ConflictPolicy::ALL,
+                    Some(self.output.conflict_policy),
+                    Message::ConflictPolicyChanged
+                ),
+                button("Save as...").on_press(Message::SaveAsButtonPressed),
+                pick_list(
+                    proxy::OutputFormat::ALL,
+                    Some(self.output.format),
+                    Message::OutputFormatChanged
+                )
+            ]
+            .push_maybe(self.output.format.is_image().then(|| {
+                column![
+                text(format!("{} DPI", self.output.image_dpi)),
+                slider(
+                    150.0..=600.0,
+                    self.output.image_dpi,
+                    Message::ImageDpiChanged
+                )
+                .step(50.0),
+                text("Grid and gutterfold layouts only, without cover page, captions and watermark")
+            ]
+                .spacing(10)
+                .align_x(Center)
+            }))
+            .spacing(10)
+            .width(Fill)
+            .align_x(Center);
+
         let mut start_button = button("Create Proxies");
 
         if self.file_path.is_some() {
             start_button = start_button.on_press(Message::StartButtonPressed);
         }
 
-        let start_button = column![start_button].width(Fill).align_x(Center);
+        let start_button = column![start_button]
+            .push_maybe(self.created_pdf.as_ref().map(text))
+            .spacing(10)
+            .width(Fill)
+            .align_x(Center);
 
-        let content = column![file_button, choose_schema, padding_slider, start_button]
-            .spacing(20)
-            .padding(20)
-            .max_width(600);
+        let content = column![
+            file_button,
+            image_dir_button,
+            choose_schema,
+            card_order,
+            card_size_settings,
+            padding_slider,
+            image_settings,
+            adjustment_settings,
+            upscaling_settings,
+            border_extension,
+            compression_settings,
+            color_settings,
+            watermark,
+            duplex,
+            card_back,
+            calibration,
+            output,
+            start_button
+        ]
+        .spacing(20)
+        .padding(20)
+        .max_width(600);
 
-        center(content).into()
+        scrollable(container(content).center_x(Fill)).into()
     }
 }
 
 #[test]
 fn change_config_properly() {
     let mut config = ProxyConfig {
-        selected_schema: false,
+        selected_schema: proxy::PageLayout::Single,
         padding_value: 50.0,
         file_path: None,
+        ..Default::default()
     };
 
-    let _ = config.update(Message::SchemaChange(true));
+    let _ = config.update(Message::SchemaChange(proxy::PageLayout::Grid));
     let _ = config.update(Message::PaddingChanged(70.0));
 
-    assert_eq!(config.selected_schema, true);
+    assert_eq!(config.selected_schema, proxy::PageLayout::Grid);
     assert_eq!(config.padding_value, 70.0)
 }
@@ -1,28 +1,265 @@
 extern crate image;
 extern crate printpdf;
 
-use std::fs::File;
-use std::io::{self, BufRead, BufWriter, Cursor};
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufWriter, Cursor, Write};
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
 use anyhow::{anyhow, bail, Context, Result};
 use futures::future::join_all;
-use image::codecs::png::PngDecoder;
 use image::io::Reader as ImageReader;
+use image::DynamicImage;
 use printpdf::*;
 use regex::Regex;
 use reqwest::{self, Client};
 use tokio::time::{sleep, Duration};
 use urlencoding::encode;
 
+mod adjustments;
+mod calibration;
+mod color;
+mod compression;
+mod cover;
+mod duplex;
+mod image_processing;
+mod layout;
+mod outline;
+mod output;
+mod raster;
+mod scryfall;
+mod shared_images;
+mod sorting;
+mod watermark;
+
+use color::OutputColor;
+use outline::OutlineEntry;
+use scryfall::{Card, Layout};
+use shared_images::SharedImages;
+
+pub use adjustments::{adjust_image, load_preview_image, AdjustmentPresets, ImageAdjustments};
+pub use calibration::{create_calibration_page, PrinterCorrection};
+pub use color::{ColorOutput, ColorSettings};
+pub use compression::{format_file_size, Compression, CompressionFormat};
+pub use duplex::{Duplex, DuplexFlip};
+pub use image_processing::{BorderExtension, BorderFit, PrintMode, Upscaling};
+pub use layout::{CardSize, CardSizePreset, GridLayout, PageLayout};
+use layout::{Rect, PAGE_MARGIN_MM};
+pub use output::{ConflictPolicy, OutputFormat, OutputSettings};
+pub use sorting::CardOrder;
+pub use watermark::{Watermark, WatermarkPosition};
+
 const CARDBACK_IMAGE: &[u8] = include_bytes!("../image/magic_card_back.png");
 const PAGE_X: f64 = 210.0;
 const PAGE_Y: f64 = 297.0;
-// Constants for card size and grid layout
+// Size of standard cards
 const CARD_WIDTH_MM: f64 = 63.0;
 const CARD_HEIGHT_MM: f64 = 88.0;
-const GRID_COLS: usize = 3;
-const GRID_ROWS: usize = 3;
+const MM_PER_INCH: f64 = 25.4;
+// Resolution the image transforms are computed for, any value works as they scale exactly
+const IMAGE_DPI: f64 = 300.0;
+const CAPTION_FONT_SIZE: f64 = 6.0;
+// Line widths in pt and the dash length of the fold line in pt
+const FOLD_LINE_THICKNESS: f64 = 0.3;
+const FOLD_LINE_DASH: i64 = 4;
+const CUT_MARK_THICKNESS: f64 = 0.25;
+// Distance of the caption baseline below the card
+const CAPTION_OFFSET_MM: f64 = 2.5;
+// File extensions accepted for local card images
+const LOCAL_IMAGE_EXTENSIONS: [&str; 4] = ["png", "jpg", "jpeg", "webp"];
+
+/// Scryfall image variants, see https://scryfall.com/docs/api/images
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ImageQuality {
+    /// 745 x 1040 PNG with transparent rounded corners, largest download
+    #[default]
+    Png,
+    /// 672 x 936 JPEG
+    Large,
+    /// 488 x 680 JPEG
+    Normal,
+    /// 480 x 680 JPEG, cropped to the card border
+    BorderCrop,
+    /// JPEG of the card art only
+    ArtCrop,
+}
+
+impl ImageQuality {
+    pub const ALL: [ImageQuality; 5] = [
+        ImageQuality::Png,
+        ImageQuality::Large,
+        ImageQuality::Normal,
+        ImageQuality::BorderCrop,
+        ImageQuality::ArtCrop,
+    ];
+
+    /// Key of the variant in Scryfall's `image_uris` object
+    fn scryfall_key(self) -> &'static str {
+        match self {
+            ImageQuality::Png => "png",
+            ImageQuality::Large => "large",
+            ImageQuality::Normal => "normal",
+            ImageQuality::BorderCrop => "border_crop",
+            ImageQuality::ArtCrop => "art_crop",
+        }
+    }
+
+    /// Image URLs of the card in this quality. Cards without the version, like some old or
+    /// digital printings, use the best other full card version instead.
+    fn card_image_urls(self, card: &Card) -> Result<CardImageUrls> {
+        let requested = card.image_urls(self.scryfall_key());
+        if requested.is_ok() {
+            return requested;
+        }
+        // the art crop doesn't replace a full card image
+        let fallback = ImageQuality::ALL
+            .into_iter()
+            .filter(|quality| *quality != self && *quality != ImageQuality::ArtCrop)
+            .find_map(|quality| Some((quality, card.image_urls(quality.scryfall_key()).ok()?)));
+        match fallback {
+            Some((quality, urls)) => {
+                println!(
+                    "No '{}' image for '{}', using '{}' instead",
+                    self.scryfall_key(),
+                    card.name,
+                    quality.scryfall_key()
+                );
+                Ok(urls)
+            }
+            None => requested,
+        }
+    }
+}
+
+impl std::fmt::Display for ImageQuality {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let label = match self {
+            ImageQuality::Png => "PNG (best quality, largest download)",
+            ImageQuality::Large => "Large JPEG (smaller download)",
+            ImageQuality::Normal => "Normal JPEG (smallest download)",
+            ImageQuality::BorderCrop => "Border crop JPEG (no rounded corners)",
+            ImageQuality::ArtCrop => "Art crop JPEG (art only)",
+        };
+        f.write_str(label)
+    }
+}
+
+/// Background used for the transparent rounded corners of Scryfall PNGs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CornerFill {
+    #[default]
+    White,
+    Black,
+    /// Average color of the card border
+    SampleBorder,
+    /// Continues the neighbouring border pixels into the corner
+    ExtendBorder,
+}
+
+impl CornerFill {
+    pub const ALL: [CornerFill; 4] = [
+        CornerFill::White,
+        CornerFill::Black,
+        CornerFill::SampleBorder,
+        CornerFill::ExtendBorder,
+    ];
+}
+
+impl std::fmt::Display for CornerFill {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let label = match self {
+            CornerFill::White => "White",
+            CornerFill::Black => "Black",
+            CornerFill::SampleBorder => "Border color",
+            CornerFill::ExtendBorder => "Extend border",
+        };
+        f.write_str(label)
+    }
+}
+
+/// Settings for a single proxy run, collected from the GUI.
+#[derive(Debug, Clone, Default)]
+pub struct ProxyOptions {
+    pub layout: PageLayout,
+    pub padding_length: f64,
+    pub card_size: CardSize,
+    pub image_quality: ImageQuality,
+    pub corner_fill: CornerFill,
+    pub border_extension: BorderExtension,
+    pub print_mode: PrintMode,
+    /// Brightness, contrast, gamma and saturation of the card images
+    pub adjustments: ImageAdjustments,
+    /// Brings low resolution images up to a print resolution
+    pub upscaling: Upscaling,
+    pub watermark: Option<Watermark>,
+    pub output: OutputSettings,
+    /// JPEG or lossless images and downsampling, for smaller PDFs
+    pub compression: Compression,
+    /// sRGB output intent or CMYK images for commercial printers
+    pub color: ColorSettings,
+    /// Order of the cards within each decklist section
+    pub card_order: CardOrder,
+    /// Moves basic lands behind the other cards of their section
+    pub basic_lands_last: bool,
+    /// Prints planes and schemes at oversized card size, on their own pages
+    pub oversized_planes: bool,
+    /// Prints the melded card on the back of meld cards
+    pub meld_backs: bool,
+    /// Includes art series cards, which are skipped by default
+    pub art_series: bool,
+    /// Draws cut marks in line with the card edges outside the cards
+    pub cut_marks: bool,
+    /// Prints name, set and collector number below each card
+    pub captions: bool,
+    /// Adds a first page with the deck name and a checklist of the cards
+    pub cover_page: bool,
+    /// Adds a back page after every card page for double-sided printing
+    pub duplex: Option<Duplex>,
+    /// Back of single faced cards on duplex and gutterfold sheets, the Magic card back if not set
+    pub back_image: Option<PathBuf>,
+    /// Scale and offset of every card placement, measured with the calibration page
+    pub printer_correction: PrinterCorrection,
+    /// Folder with custom card images that replace the Scryfall images
+    pub image_dir: Option<PathBuf>,
+}
+
+impl ProxyOptions {
+    /// Whether single faced cards get a card back behind them
+    fn prints_card_backs(&self) -> bool {
+        self.duplex.is_some() || self.layout == PageLayout::Gutterfold
+    }
+
+    /// Printed size of the card, the card size of the options unless it is an oversized plane
+    /// or turned for a card that is read sideways
+    fn card_size_of(&self, label: &CardLabel) -> CardSize {
+        match &label.card {
+            Some(card) if self.oversized_planes && card.is_oversized() => CardSize::OVERSIZED,
+            Some(card) if card.is_landscape() => self.card_size.turned(),
+            _ => self.card_size,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Decklist {
+    /// Deck name from the "About" block of MTG Arena exports
+    name: Option<String>,
+    entries: Vec<DecklistEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct DecklistEntry {
+    /// Number of copies, the number in front of the card name
+    quantity: usize,
+    card_name: String,
+    set_name: Option<String>,
+    collector_number: Option<String>,
+    /// Explicit image file given in the decklist instead of a card name
+    image_path: Option<PathBuf>,
+    /// Decklist section header above the card, e.g. "Sideboard"
+    section: Option<String>,
+}
 
 #[derive(Debug)]
 struct CardImageUrls {
@@ -30,6 +267,150 @@ struct CardImageUrls {
     back: Option<String>,
 }
 
+/// Card details used for bookmarks, captions and sorting
+#[derive(Debug, Clone, PartialEq)]
+struct CardLabel {
+    name: String,
+    set: Option<String>,
+    collector_number: Option<String>,
+    section: Option<String>,
+    /// Scryfall data of the card, not available for local images
+    card: Option<Arc<Card>>,
+}
+
+impl From<DecklistEntry> for CardLabel {
+    fn from(entry: DecklistEntry) -> Self {
+        CardLabel {
+            name: entry.card_name,
+            set: entry.set_name,
+            collector_number: entry.collector_number,
+            section: entry.section,
+            card: None,
+        }
+    }
+}
+
+impl CardLabel {
+    fn caption(&self) -> String {
+        match (&self.set, &self.collector_number) {
+            (Some(set), Some(number)) => {
+                format!("{} ({} #{})", self.name, set.to_uppercase(), number)
+            }
+            (Some(set), None) => format!("{} ({})", self.name, set.to_uppercase()),
+            _ => self.name.clone(),
+        }
+    }
+}
+
+/// Everything that goes into the PDF
+struct Deck {
+    name: String,
+    source_file: PathBuf,
+    cards: Vec<CardImage>,
+    /// Cards without image URL with their decklist quantity, listed on the cover page
+    missing: Vec<(CardLabel, usize)>,
+    /// Back of single faced cards on duplex and gutterfold sheets
+    card_back: Option<Arc<Image>>,
+    color: OutputColor,
+}
+
+/// A decklist card with the images to download, sorted before the downloads start
+struct PendingCard {
+    label: CardLabel,
+    quantity: usize,
+    /// Front face first, followed by the back face if it is printed
+    faces: Vec<ImageSource>,
+}
+
+/// A downloaded card face, ready to be placed in the PDF
+struct CardImage {
+    label: CardLabel,
+    /// Back face of a double faced card or the card back
+    back_face: bool,
+    /// Shared by all copies of the card until it is placed
+    image: Result<Arc<Image>>,
+}
+
+impl CardImage {
+    /// Another copy of the card face, sharing the decoded image.
+    fn copy(&self) -> CardImage {
+        CardImage {
+            label: self.label.clone(),
+            back_face: self.back_face,
+            image: match &self.image {
+                Ok(image) => Ok(Arc::clone(image)),
+                Err(e) => Err(anyhow!("{e:#}")),
+            },
+        }
+    }
+}
+
+#[derive(Debug)]
+enum ImageSource {
+    Url(String),
+    File(PathBuf),
+    CardBack,
+}
+
+impl From<Option<String>> for ImageSource {
+    fn from(url: Option<String>) -> Self {
+        url.map_or(ImageSource::CardBack, ImageSource::Url)
+    }
+}
+
+/// Images in the override directory, keyed by their lower-cased file stem.
+struct LocalImages(HashMap<String, PathBuf>);
+
+impl LocalImages {
+    fn load(dir: &Path) -> Result<Self> {
+        let mut images = HashMap::new();
+        let entries = fs::read_dir(dir)
+            .with_context(|| format!("Failed to read image directory '{}'", dir.display()))?;
+
+        for entry in entries {
+            let path = entry?.path();
+            if !is_image_file(&path) {
+                continue;
+            }
+            if let Some(stem) = path.file_stem().and_then(|s| s.to_str()) {
+                images.insert(stem.to_lowercase(), path);
+            }
+        }
+
+        Ok(LocalImages(images))
+    }
+
+    /// Looks up `SET-NUMBER` first, then the card name and its front face name.
+    fn find(&self, entry: &DecklistEntry) -> Option<PathBuf> {
+        let mut keys = Vec::new();
+        if let (Some(set), Some(number)) = (&entry.set_name, &entry.collector_number) {
+            keys.push(format!("{}-{}", set, number));
+        }
+        keys.push(sanitize_file_name(&entry.card_name));
+        if let Some((front_face, _)) = entry.card_name.split_once(" // ") {
+            keys.push(sanitize_file_name(front_face));
+        }
+
+        keys.iter()
+            .find_map(|key| self.0.get(&key.to_lowercase()))
+            .cloned()
+    }
+}
+
+/// Image file given in the decklist, resolved against the decklist's folder, or else the
+/// matching image of the custom image folder.
+fn local_image_path(
+    entry: &DecklistEntry,
+    decklist_dir: &Path,
+    local_images: Option<&LocalImages>,
+) -> Option<PathBuf> {
+    match &entry.image_path {
+        // joining keeps absolute paths as they are
+        Some(path) => Some(decklist_dir.join(path)),
+        None => local_images.and_then(|images| images.find(entry)),
+    }
+}
+
 #[derive(thiserror::Error, Debug, Clone)]
 #[error("PDF path could not be created")]
 pub struct PdfPathNotCreated;
@@ -47,10 +428,9 @@ static APP_USER_AGENT: &str = concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_P
 
 pub async fn main(
     file_path: Option<PathBuf>,
-    grid: bool,
-    padding_length: f64,
+    options: ProxyOptions,
 ) -> Result<PathBuf, PdfPathNotCreated> {
-    match run(file_path, grid, padding_length).await {
+    match run(file_path, options).await {
         Ok(path_buf) => Ok(path_buf),
         Err(e) => {
             eprintln!("Fatal error:\n{e:#}");
@@ -59,7 +439,7 @@ pub async fn main(
     }
 }
 
-async fn run(file_path: Option<PathBuf>, grid: bool, padding_length: f64) -> Result<PathBuf> {
+async fn run(file_path: Option<PathBuf>, options: ProxyOptions) -> Result<PathBuf> {
     let selected_file = match file_path {
         None => {
             return Err(anyhow!("Please select a .txt file including the decklist."));
@@ -69,65 +449,222 @@ async fn run(file_path: Option<PathBuf>, grid: bool, padding_length: f64) -> Res
 
     let start: std::time::Instant = std::time::Instant::now();
 
+    // Resolved before downloading, so a conflicting file fails early
+    let output_path = options
+        .output
+        .resolve_path(&selected_file, options.layout.name())?;
+    if options.output.format.is_image() {
+        if options.color.output == ColorOutput::Cmyk {
+            bail!("Page images are RGB, please choose an RGB color space.");
+        }
+        // page images have no text, the proxies must not lose their marking without notice
+        if options.watermark.is_some() {
+            bail!(
+                "Page images can't have a watermark, please save a PDF or turn off the watermark."
+            );
+        }
+        if options.captions || options.cover_page {
+            bail!("Page images can't have captions or a cover page, please save a PDF or turn them off.");
+        }
+    }
+
     let file = File::open(&selected_file)?;
 
-    let card_data = parse_text_file(file).await.map_err(|e| {
+    let decklist = parse_text_file(file).await.map_err(|e| {
         eprintln!("Error parsing the text file: {}", e);
         e
     })?;
+    let deck_name = decklist.name.clone().unwrap_or_else(|| {
+        selected_file
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    });
 
-    let mut image_futures = vec![];
+    let local_images = match &options.image_dir {
+        Some(dir) => Some(LocalImages::load(dir)?),
+        None => None,
+    };
+    // Relative image paths in the decklist are resolved against its folder
+    let decklist_dir = selected_file.parent().unwrap_or(Path::new(""));
+
+    let mut pending_cards = vec![];
+    let mut missing = vec![];
     let mut requests_count: i32 = 0;
+    let color = OutputColor::load(&options.color)?;
     let client = reqwest::Client::builder()
         .user_agent(APP_USER_AGENT)
         .build()
         .unwrap();
-    let cards_per_page = GRID_COLS * GRID_ROWS;
-
-    for (card_name, set_name) in card_data {
-        match get_card_image_url(&client, &card_name, set_name.as_deref(), "png").await {
-            Ok(card_image) => {
-                if grid {
-                    let image_future = get_card_image(client.clone(), card_image.front);
-                    image_futures.push(image_future);
-                } else {
-                    for image_url in card_image {
-                        let image_future = get_card_image(client.clone(), image_url);
-                        image_futures.push(image_future);
-                    }
-                }
 
+    for entry in decklist.entries {
+        let local_image = local_image_path(&entry, decklist_dir, local_images.as_ref());
+
+        if let Some(path) = local_image {
+            println!(
+                "Using local image '{}' for card '{}'",
+                path.display(),
+                entry.card_name
+            );
+            pending_cards.push(PendingCard {
+                quantity: entry.quantity,
+                label: CardLabel::from(entry),
+                faces: vec![ImageSource::File(path)],
+            });
+            println!();
+            continue;
+        }
+
+        let card = get_card(&client, &entry.card_name, entry.set_name.as_deref())
+            .await
+            .and_then(|card| {
+                let image_urls = options.image_quality.card_image_urls(&card)?;
+                Ok((card, image_urls))
+            });
+        match card {
+            Ok((card, mut card_image)) => {
+                requests_count += 1;
+                sleep(Duration::from_millis(50)).await;
+
+                if card.layout == Layout::ArtSeries && !options.art_series {
+                    println!("Skipping art series card '{}'", card.name);
+                    println!();
+                    continue;
+                }
                 println!(
                     "Downloading image for card '{}'{}",
-                    card_name,
-                    set_name
+                    entry.card_name,
+                    entry
+                        .set_name
+                        .as_ref()
                         .map(|s| format!(" from set '{}'", s))
                         .unwrap_or_default()
                 );
 
-                requests_count += 1;
-                sleep(Duration::from_millis(50)).await;
+                if let Some(meld_result) = card.meld_result().filter(|_| options.meld_backs) {
+                    match get_card_by_id(&client, &meld_result.id)
+                        .await
+                        .and_then(|melded| options.image_quality.card_image_urls(&melded))
+                    {
+                        Ok(melded) => card_image.back = melded.front,
+                        Err(e) => eprintln!(
+                            "Error retrieving melded card '{}' => {}",
+                            meld_result.name, e
+                        ),
+                    }
+                    requests_count += 1;
+                    sleep(Duration::from_millis(50)).await;
+                }
+
+                let quantity = entry.quantity;
+                let label = CardLabel {
+                    set: Some(card.set.clone()),
+                    collector_number: Some(card.collector_number.clone()),
+                    card: Some(Arc::new(card)),
+                    ..CardLabel::from(entry)
+                };
+
+                let faces = if options.layout == PageLayout::Grid && !options.prints_card_backs() {
+                    vec![card_image.front.into()]
+                } else {
+                    card_image
+                        .into_iter()
+                        .enumerate()
+                        // Duplex and gutterfold use the deck's card back for single faced cards
+                        .filter(|(face, image_url)| {
+                            *face == 0 || image_url.is_some() || !options.prints_card_backs()
+                        })
+                        .map(|(_, image_url)| image_url.into())
+                        .collect()
+                };
+                pending_cards.push(PendingCard {
+                    label,
+                    quantity,
+                    faces,
+                });
             }
             Err(e) => {
                 eprintln!(
-                    "Error retrieving png url for card: '{}'{} => {}",
-                    card_name,
-                    set_name
+                    "Error retrieving image url for card: '{}'{} => {}",
+                    entry.card_name,
+                    entry
+                        .set_name
+                        .as_ref()
                         .map(|s| format!(" from set '{}'", s))
                         .unwrap_or_default(),
                     e
                 );
+                let quantity = entry.quantity;
+                missing.push((CardLabel::from(entry), quantity));
             }
         }
         println!()
     }
 
-    let images: Vec<std::result::Result<Image, anyhow::Error>> = join_all(image_futures).await;
+    sorting::sort_cards(
+        &mut pending_cards,
+        options.card_order,
+        options.basic_lands_last,
+    );
+
+    let mut image_futures = vec![];
+    // Card details for each image future, in the same order
+    let mut card_labels = vec![];
+    // Number of faces and copies of each card, the images are only downloaded once
+    let mut card_copies = vec![];
+    for card in pending_cards {
+        card_copies.push((card.faces.len(), card.quantity));
+        for (face, source) in card.faces.into_iter().enumerate() {
+            image_futures.push(get_card_image(client.clone(), source));
+            card_labels.push((card.label.clone(), face > 0));
+        }
+    }
+
+    let cards: Vec<CardImage> = join_all(image_futures)
+        .await
+        .into_iter()
+        .zip(card_labels)
+        .map(|(image, (label, back_face))| CardImage {
+            image: image.and_then(|image| {
+                let card_size = options.card_size_of(&label);
+                process_card_image(image, &label.name, card_size, &color, &options).map(Arc::new)
+            }),
+            label,
+            back_face,
+        })
+        .collect();
+    let cards = repeat_copies(cards, &card_copies);
 
-    let res = if grid {
-        create_pdf_grid(&selected_file, images, cards_per_page, padding_length)
+    let card_back = if options.prints_card_backs() {
+        let source = options
+            .back_image
+            .clone()
+            .map_or(ImageSource::CardBack, ImageSource::File);
+        let image = get_card_image(client.clone(), source).await?;
+        Some(Arc::new(process_card_image(
+            image,
+            "card back",
+            options.card_size,
+            &color,
+            &options,
+        )?))
     } else {
-        create_pdf_single(&selected_file, images)
+        None
+    };
+
+    let deck = Deck {
+        name: deck_name,
+        source_file: selected_file,
+        cards,
+        missing,
+        card_back,
+        color,
+    };
+    let res = if options.output.format.is_image() {
+        page_sheets(deck.cards, &deck.card_back, &options)
+            .and_then(|sheets| raster::save_page_images(&output_path, &sheets, &options))
+    } else {
+        create_pdf(&output_path, deck, &options)
     };
 
     println!("Total number of scryfall requests: {}", requests_count);
@@ -136,115 +673,647 @@ async fn run(file_path: Option<PathBuf>, grid: bool, padding_length: f64) -> Res
     res
 }
 
-fn create_pdf_grid(
-    text_file_path: &PathBuf,
-    images: Vec<std::result::Result<Image, anyhow::Error>>,
-    cards_per_page: usize,
-    padding_length: f64,
-) -> Result<PathBuf> {
-    let (doc, mut page, mut layer) =
-        PdfDocument::new("PDF_Document_title", Mm(PAGE_X), Mm(PAGE_Y), "Layer 1");
-    for (i, image) in images.into_iter().enumerate() {
-        match image {
-            Ok(image) => {
-                if (i % cards_per_page == 0) & (i > 0) {
-                    let (new_page, new_layer) = doc.add_page(Mm(PAGE_X), Mm(PAGE_Y), "new_layer");
-                    page = new_page;
-                    layer = new_layer;
-                }
-                let current_layer_ref = doc.get_page(page).get_layer(layer);
-                // Column and row position in grid
-                let col = i % GRID_COLS;
-                let row = (i / GRID_COLS) % GRID_ROWS;
-                // Calculate spacing to center grid on the page
-                let total_grid_width =
-                    CARD_WIDTH_MM * GRID_COLS as f64 + (GRID_COLS as f64 - 1.0) * padding_length;
-                let total_grid_height = (CARD_HEIGHT_MM + padding_length) * GRID_ROWS as f64
-                    + (GRID_ROWS as f64 - 1.0) * padding_length;
-                let x_offset = (PAGE_X - total_grid_width) / 2.0;
-                let y_offset = (PAGE_Y - total_grid_height) / 2.0;
-                // Calculate position
-                let x = Mm(x_offset + (CARD_WIDTH_MM + padding_length) * col as f64);
-                let y =
-                    Mm(PAGE_Y - y_offset - (CARD_HEIGHT_MM + padding_length) * (row as f64 + 1.0));
-                image.add_to_layer(
-                    current_layer_ref,
-                    ImageTransform {
-                        translate_x: Some(x),
-                        translate_y: Some(y),
-                        ..Default::default()
-                    },
-                );
+/// Creates the PDF with the sheets of the page layout
+fn create_pdf(pdf_path: &Path, deck: Deck, options: &ProxyOptions) -> Result<PathBuf> {
+    let (doc, page, layer) = new_document(&deck.name, deck.cards.len(), options);
+    let (cover_pages, page, layer) = add_cover_page(&doc, (page, layer), &deck, options)?;
+    let mut doc_pages = Pages::new(&doc, cover_pages, page, layer);
+    let fonts = PageFonts::add_to(&doc, options)?;
+    let images = SharedImages::default();
+    let mut placed_cards = Vec::new();
+
+    for sheet in page_sheets(deck.cards, &deck.card_back, options)? {
+        let layer_ref = doc_pages.add();
+        if sheet.fold_line {
+            draw_fold_line(&layer_ref, options);
+        }
+        if options.cut_marks {
+            draw_cut_marks(&layer_ref, &sheet.slots(), options);
+        }
+        for card in sheet.cards {
+            if let Some(label) = card.label.as_ref().filter(|_| card.front) {
+                placed_cards.push((doc_pages.current(), label.clone(), false));
             }
-            Err(e) => eprintln!("Error getting image: {}", e),
+            draw_card(&layer_ref, card, &images, &fonts, options);
         }
     }
-    save_pdf(text_file_path, doc)
+
+    // single cards get a bookmark each
+    let per_card = options.layout == PageLayout::Single;
+    let outline = build_outline(cover_pages, &placed_cards, per_card);
+    save_pdf(
+        pdf_path,
+        doc,
+        images,
+        &outline,
+        &deck.color,
+        options.watermark.as_ref(),
+    )
 }
 
-fn create_pdf_single(
-    text_file_path: &PathBuf,
-    images: Vec<std::result::Result<Image, anyhow::Error>>,
-) -> Result<PathBuf> {
-    let (doc, mut page, mut layer) =
-        PdfDocument::new("PDF_Document_title", Mm(PAGE_X), Mm(PAGE_Y), "Layer 1");
-    let images_length = images.len();
-    for (i, image) in images.into_iter().enumerate() {
-        match image {
-            Ok(image) => {
-                let current_layer_ref = doc.get_page(page).get_layer(layer);
-                let x = Mm(PAGE_X / 2.0 - (CARD_WIDTH_MM / 2.0));
-                let y = Mm(PAGE_Y / 2.0 - (CARD_HEIGHT_MM / 2.0));
-                image.add_to_layer(
-                    current_layer_ref,
-                    ImageTransform {
-                        translate_x: Some(x),
-                        translate_y: Some(y),
-                        ..Default::default()
-                    },
-                );
+/// A page of cards, drawn into the PDF or into a page image
+struct Sheet {
+    cards: Vec<PlacedCard>,
+    /// Gutterfold sheets are folded along the middle of the page
+    fold_line: bool,
+}
+
+impl Sheet {
+    fn slots(&self) -> Vec<Rect> {
+        self.cards.iter().map(|card| card.slot).collect()
+    }
+}
+
+/// A card face in its slot on the page
+struct PlacedCard {
+    slot: Rect,
+    image: Arc<Image>,
+    /// Label of the card face, `None` for the card back
+    label: Option<CardLabel>,
+    /// Front faces get a bookmark
+    front: bool,
+}
+
+/// Pages of the page layout with the card of every slot
+fn page_sheets(
+    cards: Vec<CardImage>,
+    card_back: &Option<Arc<Image>>,
+    options: &ProxyOptions,
+) -> Result<Vec<Sheet>> {
+    match options.layout {
+        PageLayout::Single => single_sheets(cards, card_back, options),
+        PageLayout::Grid | PageLayout::Gutterfold => grid_sheets(cards, card_back, options),
+    }
+}
+
+/// Computes the grid and gutterfold pages with the card of every slot. Duplex printing adds
+/// a back page after every page of fronts.
+fn grid_sheets(
+    cards: Vec<CardImage>,
+    card_back: &Option<Arc<Image>>,
+    options: &ProxyOptions,
+) -> Result<Vec<Sheet>> {
+    let gutterfold = options.layout == PageLayout::Gutterfold;
+    let mut sheets = Vec::new();
+    // Landscape cards and oversized planes get their own grids on the pages after the other
+    // cards
+    let mut pairs = pair_faces(cards);
+    for card_size in [
+        options.card_size,
+        options.card_size.turned(),
+        CardSize::OVERSIZED,
+    ] {
+        let (cards, rest): (Vec<_>, Vec<_>) = pairs
+            .into_iter()
+            .partition(|(front, _)| options.card_size_of(&front.label) == card_size);
+        pairs = rest;
+        if cards.is_empty() {
+            continue;
+        }
+        let layout = if gutterfold {
+            GridLayout::gutterfold(card_size, options.padding_length)
+        } else {
+            GridLayout::fit(card_size, options.padding_length)
+        };
+        if layout.cards_per_page() == 0 {
+            bail!("Cards of {:?} don't fit on the page", card_size);
+        }
+        let pages = layout.pages(cards.len());
+        let mut cards = cards.into_iter();
 
-                if i < images_length - 1 {
-                    let (new_page, new_layer) = doc.add_page(Mm(PAGE_X), Mm(PAGE_Y), "new_layer");
-                    page = new_page;
-                    layer = new_layer;
+        for slots in pages {
+            let mut sheet = Sheet {
+                cards: Vec::new(),
+                fold_line: gutterfold,
+            };
+            let mut back_sheet = Sheet {
+                cards: Vec::new(),
+                fold_line: false,
+            };
+
+            for (slot, (front, back)) in slots.into_iter().zip(cards.by_ref()) {
+                let image = match front.image {
+                    Ok(image) => image,
+                    Err(e) => {
+                        eprintln!("Error getting image: {}", e);
+                        continue;
+                    }
+                };
+                sheet.cards.push(PlacedCard {
+                    slot,
+                    image,
+                    label: Some(front.label),
+                    front: true,
+                });
+
+                if gutterfold {
+                    let back_slot = layout.folded_slot(&slot);
+                    sheet.cards.extend(placed_back(back, card_back, back_slot));
+                } else if let Some(duplex) = &options.duplex {
+                    let back_slot = duplex.back_slot(slot);
+                    back_sheet
+                        .cards
+                        .extend(placed_back(back, card_back, back_slot));
                 }
             }
-            Err(e) => eprintln!("Error getting image: {}", e),
+
+            // Failed images leave an empty slot, the page is only added if it gets an image
+            if sheet.cards.is_empty() {
+                continue;
+            }
+            sheets.push(sheet);
+            // the back page keeps the pages in pairs, even if none of its backs could be placed
+            if options.duplex.is_some() && !gutterfold {
+                sheets.push(back_sheet);
+            }
         }
     }
-    save_pdf(text_file_path, doc)
+
+    Ok(sheets)
 }
 
-fn save_pdf(file_path: &PathBuf, doc: PdfDocumentReference) -> Result<PathBuf> {
-    let path = Path::new(file_path);
+/// One card per page. Duplex printing adds a back page after every card, without duplex the
+/// back face of a card is printed on its own page.
+fn single_sheets(
+    cards: Vec<CardImage>,
+    card_back: &Option<Arc<Image>>,
+    options: &ProxyOptions,
+) -> Result<Vec<Sheet>> {
+    let mut sheets = Vec::new();
+    let sheet = |cards: Vec<PlacedCard>| Sheet {
+        cards,
+        fold_line: false,
+    };
+
+    for (front, back) in pair_faces(cards) {
+        let card_size = options.card_size_of(&front.label);
+        let slot = match GridLayout::single(card_size).slots().first() {
+            Some(slot) => *slot,
+            None => bail!("Cards of {:?} don't fit on the page", card_size),
+        };
+        let image = match front.image {
+            Ok(image) => image,
+            Err(e) => {
+                eprintln!("Error getting image: {}", e);
+                continue;
+            }
+        };
+        sheets.push(sheet(vec![PlacedCard {
+            slot,
+            image,
+            label: Some(front.label),
+            front: true,
+        }]));
 
-    // Get the file stem (filename without extension)
-    let stem = path
-        .file_stem()
-        .and_then(|s| s.to_str())
-        .unwrap_or("output");
+        match (&options.duplex, back) {
+            (Some(duplex), back) => {
+                let back_slot = duplex.back_slot(slot);
+                let back = placed_back(back, card_back, back_slot);
+                sheets.push(sheet(back.into_iter().collect()));
+            }
+            // Without duplex the back face is printed on its own page
+            (None, Some(back)) => match back.image {
+                Ok(image) => sheets.push(sheet(vec![PlacedCard {
+                    slot,
+                    image,
+                    label: Some(back.label),
+                    front: false,
+                }])),
+                Err(e) => eprintln!("Error getting image: {}", e),
+            },
+            (None, None) => {}
+        }
+    }
 
-    let pdf_filepath = format!("{}.pdf", stem);
-    let pdf_path = PathBuf::from(&pdf_filepath);
+    Ok(sheets)
+}
 
-    let file = File::create(&pdf_filepath)?;
-    let mut writer = BufWriter::new(file);
+/// Repeats the faces of each card for its decklist quantity. Copies of double faced cards
+/// keep their faces together, front and back.
+fn repeat_copies(images: Vec<CardImage>, card_copies: &[(usize, usize)]) -> Vec<CardImage> {
+    let mut images = images.into_iter();
+    let mut cards = Vec::new();
+    for &(face_count, quantity) in card_copies {
+        let faces: Vec<CardImage> = images.by_ref().take(face_count).collect();
+        for _ in 1..quantity {
+            cards.extend(faces.iter().map(CardImage::copy));
+        }
+        if quantity > 0 {
+            cards.extend(faces);
+        }
+    }
+    cards
+}
+
+/// Front faces, each with the back face that follows it
+fn pair_faces(cards: Vec<CardImage>) -> Vec<(CardImage, Option<CardImage>)> {
+    let mut pairs = Vec::new();
+    let mut cards = cards.into_iter().peekable();
+    while let Some(front) = cards.next() {
+        let back = cards.next_if(|card| card.back_face);
+        pairs.push((front, back));
+    }
+    pairs
+}
+
+/// Hands out the pages for the cards in order, starting with the page the document was
+/// created with.
+struct Pages<'a> {
+    doc: &'a PdfDocumentReference,
+    unused: Option<(PdfPageIndex, PdfLayerIndex)>,
+    count: usize,
+}
+
+impl<'a> Pages<'a> {
+    fn new(
+        doc: &'a PdfDocumentReference,
+        cover_pages: usize,
+        page: PdfPageIndex,
+        layer: PdfLayerIndex,
+    ) -> Self {
+        Pages {
+            doc,
+            unused: Some((page, layer)),
+            count: cover_pages,
+        }
+    }
+
+    fn add(&mut self) -> PdfLayerReference {
+        let (page, layer) = self
+            .unused
+            .take()
+            .unwrap_or_else(|| self.doc.add_page(Mm(PAGE_X), Mm(PAGE_Y), "Cards"));
+        self.count += 1;
+        self.doc.get_page(page).get_layer(layer)
+    }
+
+    /// Zero based index of the last added page
+    fn current(&self) -> usize {
+        self.count - 1
+    }
+}
+
+/// Places a card image into the slot, with printer correction applied. Copies of a card
+/// share the image, which is embedded once.
+fn place_image(
+    layer: &PdfLayerReference,
+    images: &SharedImages,
+    image: Arc<Image>,
+    slot: &Rect,
+    options: &ProxyOptions,
+) {
+    let transform = image_transform(image.image.width, image.image.height, slot, options);
+    images.place(layer, image, transform);
+}
+
+/// Printed area of a card image on the page, in mm from the bottom left page corner.
+struct Placement {
+    x: f64,
+    y: f64,
+    /// Size on the page, landscape images are printed turned
+    width: f64,
+    height: f64,
+    /// Images that don't match the orientation of the slot are turned a quarter: landscape
+    /// images like planes to the left into portrait slots, so the image width runs up the
+    /// slot, and portrait images like split cards to the right into landscape slots
+    rotated: bool,
+}
+
+/// Area of the slot with the border extension reaching past it and printer correction
+/// applied.
+fn card_placement(landscape_image: bool, slot: &Rect, options: &ProxyOptions) -> Placement {
+    let image_offset = options.border_extension.overflow_mm();
+    let image = options.printer_correction.apply_to_rect(&Rect {
+        x: slot.x - image_offset,
+        y: slot.y - image_offset,
+        width: slot.width + 2.0 * image_offset,
+        height: slot.height + 2.0 * image_offset,
+    });
+    Placement {
+        x: image.x,
+        y: image.y,
+        width: image.width,
+        height: image.height,
+        rotated: landscape_image != (slot.width > slot.height),
+    }
+}
+
+/// Transform that scales an image of any resolution to exactly the size of the slot, plus
+/// the border extension reaching past it.
+fn image_transform(width: Px, height: Px, slot: &Rect, options: &ProxyOptions) -> ImageTransform {
+    let placement = card_placement(width.0 > height.0, slot, options);
+    // Size of the image at the DPI of the transform, before scaling
+    let natural_size = |px: Px| Mm::from(px.into_pt(IMAGE_DPI)).0;
+
+    if placement.rotated && placement.width > placement.height {
+        // The turn to the right around the bottom left image corner moves the image down by
+        // its printed height
+        ImageTransform {
+            translate_x: Some(Mm(placement.x)),
+            translate_y: Some(Mm(placement.y + placement.height)),
+            rotate: Some(ImageRotation {
+                angle_ccw_degrees: -90.0,
+                ..Default::default()
+            }),
+            scale_x: Some(placement.height / natural_size(width)),
+            scale_y: Some(placement.width / natural_size(height)),
+            dpi: Some(IMAGE_DPI),
+        }
+    } else if placement.rotated {
+        // The turn to the left around the bottom left image corner moves the image left by its
+        // printed width
+        ImageTransform {
+            translate_x: Some(Mm(placement.x + placement.width)),
+            translate_y: Some(Mm(placement.y)),
+            rotate: Some(ImageRotation {
+                angle_ccw_degrees: 90.0,
+                ..Default::default()
+            }),
+            scale_x: Some(placement.height / natural_size(width)),
+            scale_y: Some(placement.width / natural_size(height)),
+            dpi: Some(IMAGE_DPI),
+        }
+    } else {
+        ImageTransform {
+            translate_x: Some(Mm(placement.x)),
+            translate_y: Some(Mm(placement.y)),
+            rotate: None,
+            scale_x: Some(placement.width / natural_size(width)),
+            scale_y: Some(placement.height / natural_size(height)),
+            dpi: Some(IMAGE_DPI),
+        }
+    }
+}
+
+/// Start and end of the fold line in the middle of gutterfold sheets, with printer
+/// correction applied
+fn fold_line(options: &ProxyOptions) -> [(f64, f64); 2] {
+    let correction = options.printer_correction;
+    [PAGE_MARGIN_MM, PAGE_Y - PAGE_MARGIN_MM].map(|y| correction.apply(PAGE_X / 2.0, y))
+}
+
+/// Cut marks around the slots, outside of the border extension and with printer correction
+/// applied
+fn cut_marks(slots: &[Rect], options: &ProxyOptions) -> Vec<[(f64, f64); 2]> {
+    let correction = options.printer_correction;
+    layout::cut_marks(slots, options.border_extension.overflow_mm())
+        .into_iter()
+        .map(|mark| mark.map(|(x, y)| correction.apply(x, y)))
+        .collect()
+}
+
+fn draw_cut_marks(layer: &PdfLayerReference, slots: &[Rect], options: &ProxyOptions) {
+    layer.save_graphics_state();
+    layer.set_outline_thickness(CUT_MARK_THICKNESS);
+    for mark in cut_marks(slots, options) {
+        layer.add_shape(Line {
+            points: mark
+                .map(|(x, y)| (Point::new(Mm(x), Mm(y)), false))
+                .to_vec(),
+            is_closed: false,
+            has_fill: false,
+            has_stroke: true,
+            is_clipping_path: false,
+        });
+    }
+    layer.restore_graphics_state();
+}
+
+/// Dashed line along the middle of gutterfold sheets
+fn draw_fold_line(layer: &PdfLayerReference, options: &ProxyOptions) {
+    let points = fold_line(options)
+        .map(|(x, y)| (Point::new(Mm(x), Mm(y)), false))
+        .to_vec();
+
+    layer.save_graphics_state();
+    layer.set_outline_thickness(FOLD_LINE_THICKNESS);
+    layer.set_line_dash_pattern(LineDashPattern {
+        dash_1: Some(FOLD_LINE_DASH),
+        ..LineDashPattern::default()
+    });
+    layer.add_shape(Line {
+        points,
+        is_closed: false,
+        has_fill: false,
+        has_stroke: true,
+        is_clipping_path: false,
+    });
+    layer.restore_graphics_state();
+}
+
+/// The back face of a double faced card, or the card back behind single faced cards.
+fn placed_back(
+    back: Option<CardImage>,
+    card_back: &Option<Arc<Image>>,
+    slot: Rect,
+) -> Option<PlacedCard> {
+    match (back, card_back) {
+        (Some(back), _) => match back.image {
+            Ok(image) => Some(PlacedCard {
+                slot,
+                image,
+                label: Some(back.label),
+                front: false,
+            }),
+            Err(e) => {
+                eprintln!("Error getting image: {}", e);
+                None
+            }
+        },
+        (None, Some(card_back)) => Some(PlacedCard {
+            slot,
+            image: Arc::clone(card_back),
+            label: None,
+            front: false,
+        }),
+        (None, None) => None,
+    }
+}
+
+/// Places the card image with its watermark and caption, the card back gets neither.
+fn draw_card(
+    layer: &PdfLayerReference,
+    card: PlacedCard,
+    images: &SharedImages,
+    fonts: &PageFonts,
+    options: &ProxyOptions,
+) {
+    place_image(layer, images, card.image, &card.slot, options);
+    if let Some(label) = &card.label {
+        fonts.decorate(layer, options, label, &card.slot);
+    }
+}
+
+fn new_document(
+    deck_name: &str,
+    card_count: usize,
+    options: &ProxyOptions,
+) -> (PdfDocumentReference, PdfPageIndex, PdfLayerIndex) {
+    let first_layer = if options.cover_page {
+        "Checklist"
+    } else {
+        "Cards"
+    };
+    let (doc, page, layer) = PdfDocument::new(deck_name, Mm(PAGE_X), Mm(PAGE_Y), first_layer);
+    let author = std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_else(|_| env!("CARGO_PKG_NAME").to_string());
+    let created = OffsetDateTime::try_now_local().unwrap_or_else(|_| OffsetDateTime::now_utc());
+
+    let doc = doc
+        .with_author(author)
+        .with_creator(APP_USER_AGENT)
+        .with_subject(format!(
+            "MTG proxies for {}, {} card images, {} layout",
+            deck_name,
+            card_count,
+            options.layout.name()
+        ))
+        .with_creation_date(created);
+    (doc, page, layer)
+}
+
+/// Draws the cover page onto the first page if enabled. Returns the number of cover pages
+/// and the page for the first cards.
+fn add_cover_page(
+    doc: &PdfDocumentReference,
+    (first_page, first_layer): (PdfPageIndex, PdfLayerIndex),
+    deck: &Deck,
+    options: &ProxyOptions,
+) -> Result<(usize, PdfPageIndex, PdfLayerIndex)> {
+    if !options.cover_page {
+        return Ok((0, first_page, first_layer));
+    }
+
+    let checklist = cover::Checklist::new(&deck.cards, &deck.missing);
+    let cover_pages = cover::draw_cover(
+        doc,
+        first_page,
+        first_layer,
+        &deck.name,
+        &deck.source_file,
+        &checklist,
+    )?;
+    let (page, layer) = doc.add_page(Mm(PAGE_X), Mm(PAGE_Y), "Cards");
+    Ok((cover_pages, page, layer))
+}
+
+/// Fonts for the text drawn on top of the cards, only added to the PDF when used.
+struct PageFonts {
+    watermark: Option<IndirectFontRef>,
+    caption: Option<IndirectFontRef>,
+}
+
+impl PageFonts {
+    fn add_to(doc: &PdfDocumentReference, options: &ProxyOptions) -> Result<Self> {
+        let watermark = match options.watermark {
+            Some(_) => Some(doc.add_builtin_font(BuiltinFont::HelveticaBold)?),
+            None => None,
+        };
+        let caption = if options.captions {
+            Some(doc.add_builtin_font(BuiltinFont::Helvetica)?)
+        } else {
+            None
+        };
+        Ok(PageFonts { watermark, caption })
+    }
+
+    /// Draws the watermark and caption for the card slot, onto the card where the printer
+    /// correction places it.
+    fn decorate(
+        &self,
+        layer: &PdfLayerReference,
+        options: &ProxyOptions,
+        label: &CardLabel,
+        slot: &Rect,
+    ) {
+        let card = options.printer_correction.apply_to_rect(slot);
+        if let (Some(watermark), Some(font)) = (&options.watermark, &self.watermark) {
+            watermark.stamp(layer, font, &card);
+        }
+        if let Some(font) = &self.caption {
+            layer.use_text(
+                label.caption(),
+                CAPTION_FONT_SIZE,
+                Mm(card.x),
+                Mm(card.y - CAPTION_OFFSET_MM),
+                font,
+            );
+        }
+    }
+}
+
+/// Bookmarks per decklist section, with one child per card if `per_card` is set.
+fn build_outline(
+    cover_pages: usize,
+    placed_cards: &[(usize, CardLabel, bool)],
+    per_card: bool,
+) -> Vec<OutlineEntry> {
+    let mut outline: Vec<OutlineEntry> = Vec::new();
+    if cover_pages > 0 {
+        outline.push(OutlineEntry::new("Checklist".to_string(), 0));
+    }
+    let mut current_section = None;
 
-    doc.save(&mut writer).map_err(|e| {
-        eprintln!("There was an error opening the file: {}", e);
+    for (page, label, back_face) in placed_cards {
+        if label.section.is_some() && label.section != current_section {
+            current_section = label.section.clone();
+            outline.push(OutlineEntry::new(label.section.clone().unwrap(), *page));
+        }
+        if !per_card || *back_face {
+            continue;
+        }
+
+        let entry = OutlineEntry::new(label.name.clone(), *page);
+        match (&current_section, outline.last_mut()) {
+            (Some(_), Some(section)) => section.children.push(entry),
+            _ => outline.push(entry),
+        }
+    }
+
+    outline
+}
+
+fn save_pdf(
+    pdf_path: &Path,
+    doc: PdfDocumentReference,
+    images: SharedImages,
+    outline: &[OutlineEntry],
+    color: &OutputColor,
+    watermark: Option<&Watermark>,
+) -> Result<PathBuf> {
+    if let Some(dir) = pdf_path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create output folder '{}'", dir.display()))?;
+    }
+
+    let pdf_bytes = doc.save_to_bytes().map_err(|e| {
+        eprintln!("There was an error creating the PDF: {}", e);
         e
     })?;
+    let pdf_bytes = images.embed(pdf_bytes)?;
+    let pdf_bytes = outline::add_outline(pdf_bytes, outline)?;
+    let pdf_bytes = match watermark {
+        Some(watermark) => watermark.add_opacity(pdf_bytes)?,
+        None => pdf_bytes,
+    };
+    let pdf_bytes = color.add_output_intent(pdf_bytes)?;
+    let pdf_bytes = compression::compress_pdf(pdf_bytes)?;
+
+    let file = File::create(pdf_path)
+        .with_context(|| format!("Failed to create '{}'", pdf_path.display()))?;
+    let mut writer = BufWriter::new(file);
+    writer.write_all(&pdf_bytes)?;
 
-    Ok(pdf_path)
+    println!(
+        "Saved PDF to '{}' ({})",
+        pdf_path.display(),
+        format_file_size(pdf_bytes.len() as u64)
+    );
+    Ok(pdf_path.to_path_buf())
 }
 
-async fn get_card_image_url(
-    client: &Client,
-    card_name: &str,
-    set_name: Option<&str>,
-    image_file_type: &str,
-) -> Result<CardImageUrls> {
+async fn get_card(client: &Client, card_name: &str, set_name: Option<&str>) -> Result<Card> {
     println!(
         "Creating image URL for card '{}'{}",
         card_name,
@@ -269,9 +1338,18 @@ async fn get_card_image_url(
     };
 
     println!("[Scryfall API] Requesting image URL from: '{}'", url);
+    request_card(client, &url).await
+}
 
+async fn get_card_by_id(client: &Client, id: &str) -> Result<Card> {
+    let url = format!("https://api.scryfall.com/cards/{}", encode(id));
+    println!("[Scryfall API] Requesting card from: '{}'", url);
+    request_card(client, &url).await
+}
+
+async fn request_card(client: &Client, url: &str) -> Result<Card> {
     let res = client
-        .get(&url)
+        .get(url)
         .send()
         .await
         .context("Failed to make request to Scryfall API")?;
@@ -279,51 +1357,7 @@ async fn get_card_image_url(
     println!("Scryfall Request Response Satus: {}", res.status());
 
     if res.status().is_success() {
-        let data: serde_json::Value = res.json().await.context("Failed to parse JSON response")?;
-
-        if let Some(image_uris) = data["image_uris"].as_object() {
-            if let Some(png_url) = image_uris.get(image_file_type) {
-                return Ok(CardImageUrls {
-                    front: Some(
-                        png_url
-                            .as_str()
-                            .ok_or_else(|| anyhow!("Image URL is not a valid string"))?
-                            .to_string(),
-                    ),
-                    back: None,
-                });
-            }
-        }
-
-        if let Some(card_faces) = data["card_faces"].as_array() {
-            let mut front_image_urls: Vec<String> = Vec::new();
-
-            for card_face in card_faces {
-                let image_uris = card_face["image_uris"]
-                    .as_object()
-                    .context("Field 'image_uris' not found in JSON response")?;
-                if let Some(png_url) = image_uris.get(image_file_type) {
-                    front_image_urls.push(
-                        png_url
-                            .as_str()
-                            .ok_or_else(|| anyhow!("Image URL is not a valid string"))?
-                            .to_string(),
-                    );
-                }
-            }
-
-            // Check for card back images if present
-            if front_image_urls.len() == 2 {
-                let front = front_image_urls.get(0).cloned();
-                let back = front_image_urls.get(1).cloned();
-                return Ok(CardImageUrls { front, back });
-            } else {
-                let front = front_image_urls.get(0).cloned();
-                return Ok(CardImageUrls { front, back: None });
-            }
-        }
-        // If no image_uris or card_faces were found
-        bail!("Image URLs not found in JSON response");
+        res.json().await.context("Failed to parse JSON response")
     } else {
         bail!(
             "Error: Failed to retrieve card data. Status Code: {}",
@@ -332,51 +1366,159 @@ async fn get_card_image_url(
     }
 }
 
-async fn get_card_image(client: Client, png_url: Option<String>) -> Result<Image> {
-    if let Some(url) = png_url {
-        println!("[Download] Downloading image from URL: {}", url);
-        // downloading image from url to bytes
-        let response = client
-            .get(url)
-            .send()
-            .await
-            .context("Failed to fetch image from URL")?;
-        let img_bytes = response
-            .bytes()
-            .await
-            .context("Could not convert URL to bytes")?;
+async fn get_card_image(client: Client, source: ImageSource) -> Result<DynamicImage> {
+    let dynamic_image = match source {
+        ImageSource::Url(url) => {
+            println!("[Download] Downloading image from URL: {}", url);
+            // downloading image from url to bytes
+            let response = client
+                .get(url)
+                .send()
+                .await
+                .context("Failed to fetch image from URL")?;
+            let img_bytes = response
+                .bytes()
+                .await
+                .context("Could not convert URL to bytes")?;
 
-        // transforming image bytes to image format required by printpdf
-        let mut reader = Cursor::new(img_bytes);
+            image::load_from_memory(&img_bytes).context("Failed to decode downloaded image")?
+        }
+        ImageSource::File(path) => {
+            println!("[Local] Loading image from file: {}", path.display());
+            let img_bytes = tokio::fs::read(&path)
+                .await
+                .with_context(|| format!("Failed to read image file '{}'", path.display()))?;
 
-        let decoder = PngDecoder::new(&mut reader).context("Failed to create PNG decoder")?;
+            image::load_from_memory(&img_bytes)
+                .with_context(|| format!("Failed to decode image file '{}'", path.display()))?
+        }
+        ImageSource::CardBack => {
+            println!("[Download] Using local card back image.");
+            let img_reader = ImageReader::new(Cursor::new(CARDBACK_IMAGE))
+                .with_guessed_format()
+                .context("Failed to open card back image")?;
+            img_reader
+                .decode()
+                .context("Failed to decode local image")?
+        }
+    };
 
-        let mut image = Image::try_from(decoder).context("Failed to create image from data")?;
+    Ok(dynamic_image)
+}
 
-        image.image = remove_alpha_channel_from_image_x_object(image.image);
-        Ok(image)
+// image processing steps between download and layout
+fn process_card_image(
+    dynamic_image: DynamicImage,
+    name: &str,
+    card_size: CardSize,
+    color: &OutputColor,
+    options: &ProxyOptions,
+) -> Result<Image> {
+    // Images are turned when they are placed into a slot of the other orientation, their
+    // width is the card height then
+    let landscape_image = dynamic_image.width() > dynamic_image.height();
+    let image_width_mm = if landscape_image == card_size.is_landscape() {
+        card_size.width_mm
     } else {
-        println!("[Download] Using local card back image.");
-        let img_reader = ImageReader::new(Cursor::new(CARDBACK_IMAGE))
-            .with_guessed_format()
-            .context("Failed to open card back image")?;
-        let dynamic_image = img_reader
-            .decode()
-            .context("Failed to decode local image")?;
-        let mut image = Image::from_dynamic_image(&dynamic_image);
-        image.image = remove_alpha_channel_from_image_x_object(image.image);
-        Ok(image)
+        card_size.height_mm
+    };
+    let dpi = image_processing::effective_dpi(&dynamic_image, image_width_mm);
+    if dpi < options.upscaling.min_dpi {
+        eprintln!(
+            "Warning: The image of '{}' has only {:.0} DPI at card size and may print blurry",
+            name, dpi
+        );
+    }
+    let dynamic_image = image_processing::upscale(dynamic_image, image_width_mm, options.upscaling);
+    let dynamic_image =
+        compression::downsample(dynamic_image, image_width_mm, options.compression.max_dpi());
+    let dynamic_image = adjustments::adjust_image(dynamic_image, options.adjustments);
+    let dynamic_image =
+        image_processing::extend_border(dynamic_image, options.border_extension, image_width_mm);
+    let mut image = image_from_dynamic_image(dynamic_image, options.corner_fill);
+    image.image = image_processing::apply_print_mode(image.image, options.print_mode);
+    image.image = color.convert(image.image);
+    // page images are encoded as a whole, JPEG card images would be compressed twice
+    if !options.output.format.is_image() {
+        image.image = compression::compress_image(image.image, options.compression)?;
     }
+    Ok(image)
 }
 
-async fn parse_text_file(file: File) -> io::Result<Vec<(String, Option<String>)>> {
-    let mut card_details = Vec::new();
+// transforming a decoded image to the image format required by printpdf
+fn image_from_dynamic_image(dynamic_image: DynamicImage, corner_fill: CornerFill) -> Image {
+    // the alpha removal works on 8 bit channels only
+    let dynamic_image = match dynamic_image {
+        DynamicImage::ImageLuma16(_) => DynamicImage::ImageLuma8(dynamic_image.to_luma8()),
+        DynamicImage::ImageLumaA16(_) => DynamicImage::ImageLumaA8(dynamic_image.to_luma_alpha8()),
+        DynamicImage::ImageRgb16(_) | DynamicImage::ImageRgb32F(_) => {
+            DynamicImage::ImageRgb8(dynamic_image.to_rgb8())
+        }
+        DynamicImage::ImageRgba16(_) | DynamicImage::ImageRgba32F(_) => {
+            DynamicImage::ImageRgba8(dynamic_image.to_rgba8())
+        }
+        other => other,
+    };
+    let mut image = Image::from_dynamic_image(&dynamic_image);
+    image.image = remove_alpha_channel_from_image_x_object(image.image, corner_fill);
+    image
+}
+
+async fn parse_text_file(file: File) -> io::Result<Decklist> {
+    parse_decklist(io::BufReader::new(file))
+}
+
+fn parse_decklist(reader: impl BufRead) -> io::Result<Decklist> {
+    let mut decklist = Decklist::default();
+    let mut section: Option<String> = None;
+    let image_path_pattern =
+        Regex::new(r"^\s*\d+\s+(file://.+|.+\.(?i:png|jpe?g|webp))\s*$").unwrap();
     let card_pattern_with_set = Regex::new(r"\d (.*) \(").unwrap();
     let card_pattern_without_set = Regex::new(r"\d (.*)").unwrap();
-    let set_pattern = Regex::new(r"\(([a-zA-Z0-9]*)\)").unwrap();
+    let set_pattern = Regex::new(r"\(([a-zA-Z0-9]*)\)(?:\s+([^\s(]+))?").unwrap();
 
-    for line in io::BufReader::new(file).lines() {
+    for line in reader.lines() {
         let line = line?;
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+
+        // Lines without a quantity are section headers like "Sideboard" or "// Creatures"
+        if !trimmed.starts_with(|c: char| c.is_ascii_digit()) {
+            match (section.as_deref(), trimmed.strip_prefix("Name ")) {
+                (Some("About"), Some(deck_name)) => decklist.name = Some(deck_name.to_string()),
+                _ => {
+                    let header = trimmed.trim_start_matches('/').trim_end_matches(':').trim();
+                    section = Some(header.to_string());
+                }
+            }
+            continue;
+        }
+
+        let quantity = trimmed
+            .split(|c: char| !c.is_ascii_digit())
+            .next()
+            .and_then(|digits| digits.parse().ok())
+            .unwrap_or(1);
+
+        // Lines like "1 art/my_alter.png" or "1 file:///home/me/alter.jpg"
+        if let Some(path_match) = image_path_pattern.captures(&line) {
+            let image_path = path_from_uri(&path_match[1]);
+            let card_name = image_path
+                .file_stem()
+                .map(|s| s.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            decklist.entries.push(DecklistEntry {
+                quantity,
+                card_name,
+                set_name: None,
+                collector_number: None,
+                image_path: Some(image_path),
+                section: section.clone(),
+            });
+            continue;
+        }
 
         // TODO: Check if this causes problems with certain decklist formats
         // Choose regex based on presence of '('
@@ -388,40 +1530,113 @@ async fn parse_text_file(file: File) -> io::Result<Vec<(String, Option<String>)>
 
         if let Some(card_match) = card_pattern.captures(&line) {
             let card_name = card_match[1].trim().to_string();
-            let set_name = set_pattern.captures(&line).map(|cap| cap[1].to_string());
-            card_details.push((card_name, set_name))
+            let set_match = set_pattern.captures(&line);
+            let set_name = set_match.as_ref().map(|cap| cap[1].to_string());
+            let collector_number = set_match
+                .as_ref()
+                .and_then(|cap| cap.get(2))
+                .map(|m| m.as_str().to_string());
+            decklist.entries.push(DecklistEntry {
+                quantity,
+                card_name,
+                set_name,
+                collector_number,
+                image_path: None,
+                section: section.clone(),
+            })
         } else {
             // Handle lines that don't match the expected format
             eprintln!("Warning: Skipped line - {}", line.trim());
         }
     }
 
-    Ok(card_details)
+    Ok(decklist)
+}
+
+fn path_from_uri(uri: &str) -> PathBuf {
+    let uri = uri.trim();
+    match uri.strip_prefix("file://") {
+        Some(path) => {
+            let path = urlencoding::decode(path)
+                .map(|p| p.into_owned())
+                .unwrap_or_else(|_| path.to_string());
+            // file:///C:/cards/bolt.png on Windows
+            match path.strip_prefix('/') {
+                Some(rest) if rest.get(1..2) == Some(":") => PathBuf::from(rest),
+                _ => PathBuf::from(path),
+            }
+        }
+        None => PathBuf::from(uri),
+    }
+}
+
+fn is_image_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| LOCAL_IMAGE_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+}
+
+// Replaces characters that are not allowed in file names, e.g. in "Fire // Ice"
+fn sanitize_file_name(name: &str) -> String {
+    name.chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c => c,
+        })
+        .collect()
 }
 
-// taken from https://github.com/fschutt/printpdf/issues/119
-pub fn remove_alpha_channel_from_image_x_object(image_x_object: ImageXObject) -> ImageXObject {
-    if !matches!(image_x_object.color_space, ColorSpace::Rgba) {
-        return image_x_object;
+// adapted from https://github.com/fschutt/printpdf/issues/119
+pub fn remove_alpha_channel_from_image_x_object(
+    image_x_object: ImageXObject,
+    corner_fill: CornerFill,
+) -> ImageXObject {
+    let channels = match image_x_object.color_space {
+        ColorSpace::Rgba => 4,
+        ColorSpace::GreyscaleAlpha => 2,
+        _ => return image_x_object,
     };
     let ImageXObject {
+        width,
+        height,
         color_space,
         image_data,
         ..
     } = image_x_object;
+    let pixels = AlphaPixels {
+        data: &image_data,
+        width: width.0,
+        height: height.0,
+        channels,
+    };
 
-    let new_image_data = image_data
-        .chunks(4)
-        .map(|rgba| {
-            let [red, green, blue, alpha]: [u8; 4] = rgba.try_into().ok().unwrap();
-            let alpha = alpha as f64 / 255.0;
-            let new_red = ((1.0 - alpha) * 255.0 + alpha * red as f64) as u8;
-            let new_green = ((1.0 - alpha) * 255.0 + alpha * green as f64) as u8;
-            let new_blue = ((1.0 - alpha) * 255.0 + alpha * blue as f64) as u8;
-            return [new_red, new_green, new_blue];
-        })
-        .collect::<Vec<[u8; 3]>>()
-        .concat();
+    let border_color = pixels.sample_border_color();
+    let mut new_image_data = Vec::with_capacity(image_data.len() / channels * (channels - 1));
+
+    for (i, pixel) in image_data.chunks_exact(channels).enumerate() {
+        let (color, alpha) = pixel.split_at(channels - 1);
+        if alpha[0] == u8::MAX {
+            new_image_data.extend_from_slice(color);
+            continue;
+        }
+
+        let background = match corner_fill {
+            CornerFill::White => vec![u8::MAX; channels - 1],
+            CornerFill::Black => vec![0; channels - 1],
+            CornerFill::SampleBorder => border_color.clone(),
+            CornerFill::ExtendBorder => pixels
+                .nearest_opaque_color(i % pixels.width, i / pixels.width)
+                .unwrap_or_else(|| border_color.clone()),
+        };
+
+        let alpha = alpha[0] as f64 / 255.0;
+        new_image_data.extend(
+            color
+                .iter()
+                .zip(&background)
+                .map(|(&c, &b)| ((1.0 - alpha) * b as f64 + alpha * c as f64) as u8),
+        );
+    }
 
     let new_color_space = match color_space {
         ColorSpace::Rgba => ColorSpace::Rgb,
@@ -435,3 +1650,764 @@ pub fn remove_alpha_channel_from_image_x_object(image_x_object: ImageXObject) ->
         ..image_x_object
     }
 }
+
+/// 8 bit image data with the alpha value as last channel of each pixel
+struct AlphaPixels<'a> {
+    data: &'a [u8],
+    width: usize,
+    height: usize,
+    channels: usize,
+}
+
+impl AlphaPixels<'_> {
+    fn pixel(&self, x: usize, y: usize) -> &[u8] {
+        let start = (y * self.width + x) * self.channels;
+        &self.data[start..start + self.channels]
+    }
+
+    fn opaque_color(&self, x: usize, y: usize) -> Option<&[u8]> {
+        let (color, alpha) = self.pixel(x, y).split_at(self.channels - 1);
+        (alpha[0] == u8::MAX).then_some(color)
+    }
+
+    /// Averages the opaque pixels a few pixels inside the middle of each card edge,
+    /// where the card frame is.
+    fn sample_border_color(&self) -> Vec<u8> {
+        let mut sum = vec![0u64; self.channels - 1];
+        let mut count = 0;
+        if self.width == 0 || self.height == 0 {
+            return vec![u8::MAX; self.channels - 1];
+        }
+        let inset = (self.width / 60)
+            .max(1)
+            .min(self.width.min(self.height) / 2);
+
+        let vertical = (self.height / 4..self.height * 3 / 4)
+            .flat_map(|y| [(inset, y), (self.width - 1 - inset, y)]);
+        let horizontal = (self.width / 4..self.width * 3 / 4)
+            .flat_map(|x| [(x, inset), (x, self.height - 1 - inset)]);
+
+        for (x, y) in vertical.chain(horizontal) {
+            if let Some(color) = self.opaque_color(x, y) {
+                sum.iter_mut().zip(color).for_each(|(s, &c)| *s += c as u64);
+                count += 1;
+            }
+        }
+
+        if count == 0 {
+            return vec![u8::MAX; self.channels - 1];
+        }
+        sum.iter().map(|s| (s / count) as u8).collect()
+    }
+
+    /// Walks from the pixel towards the image center and returns the first opaque color,
+    /// which continues the card border into the rounded corner.
+    fn nearest_opaque_color(&self, x: usize, y: usize) -> Option<Vec<u8>> {
+        let (cx, cy) = ((self.width / 2) as i64, (self.height / 2) as i64);
+        let (dx, dy) = (cx - x as i64, cy - y as i64);
+        let steps = dx.abs().max(dy.abs());
+
+        (1..=steps).find_map(|step| {
+            let px = x as i64 + dx * step / steps;
+            let py = y as i64 + dy * step / steps;
+            self.opaque_color(px as usize, py as usize)
+                .map(|color| color.to_vec())
+        })
+    }
+}
+
+#[test]
+fn remove_alpha_from_greyscale_alpha_image() {
+    // transparent black, opaque black, half transparent black
+    let image_x_object = ImageXObject::new(
+        Px(3),
+        Px(1),
+        ColorSpace::GreyscaleAlpha,
+        ColorBits::Bit8,
+        true,
+        None,
+        None,
+        vec![0, 0, 0, 255, 0, 128],
+    );
+
+    let white = remove_alpha_channel_from_image_x_object(image_x_object.clone(), CornerFill::White);
+    let black = remove_alpha_channel_from_image_x_object(image_x_object, CornerFill::Black);
+
+    assert!(matches!(white.color_space, ColorSpace::Greyscale));
+    assert_eq!(white.image_data, vec![255, 0, 127]);
+    assert_eq!(black.image_data, vec![0, 0, 0]);
+}
+
+#[test]
+fn transparent_corners_take_the_border_color() {
+    // red frame around blue art, with the three pixels in each corner transparent
+    let (width, height) = (60, 80);
+    let frame = [200, 30, 30];
+    let art = [10, 10, 200];
+    let mut data = Vec::with_capacity(width * height * 4);
+    for y in 0..height {
+        for x in 0..width {
+            let (edge_x, edge_y) = (x.min(width - 1 - x), y.min(height - 1 - y));
+            if edge_x < 3 && edge_y < 3 {
+                data.extend_from_slice(&[0, 0, 0, 0]);
+            } else if edge_x < 4 || edge_y < 4 {
+                data.extend_from_slice(&[frame[0], frame[1], frame[2], 255]);
+            } else {
+                data.extend_from_slice(&[art[0], art[1], art[2], 255]);
+            }
+        }
+    }
+    let image_x_object = ImageXObject::new(
+        Px(width),
+        Px(height),
+        ColorSpace::Rgba,
+        ColorBits::Bit8,
+        true,
+        None,
+        None,
+        data,
+    );
+    let pixel = |image: &ImageXObject, x: usize, y: usize| {
+        let start = (y * width + x) * 3;
+        image.image_data[start..start + 3].to_vec()
+    };
+
+    for corner_fill in [CornerFill::SampleBorder, CornerFill::ExtendBorder] {
+        let image = remove_alpha_channel_from_image_x_object(image_x_object.clone(), corner_fill);
+
+        assert!(matches!(image.color_space, ColorSpace::Rgb));
+        for (x, y) in [
+            (0, 0),
+            (width - 1, 0),
+            (0, height - 1),
+            (width - 1, height - 1),
+        ] {
+            assert_eq!(
+                pixel(&image, x, y),
+                frame,
+                "corner {x},{y} with {corner_fill:?}"
+            );
+        }
+        assert_eq!(pixel(&image, width / 2, height / 2), art);
+    }
+}
+
+#[test]
+fn only_planes_and_schemes_are_printed_oversized() {
+    let label = |layout| CardLabel {
+        name: "Tazeem".to_string(),
+        set: None,
+        collector_number: None,
+        section: None,
+        card: Some(Arc::new(Card {
+            layout,
+            ..Card::default()
+        })),
+    };
+    let options = ProxyOptions {
+        oversized_planes: true,
+        ..Default::default()
+    };
+
+    assert_eq!(
+        options.card_size_of(&label(Layout::Planar)),
+        CardSize::OVERSIZED
+    );
+    assert_eq!(
+        options.card_size_of(&label(Layout::Scheme)),
+        CardSize::OVERSIZED
+    );
+    assert_eq!(
+        options.card_size_of(&label(Layout::Battle)),
+        CardSize::STANDARD.turned()
+    );
+    assert_eq!(
+        ProxyOptions::default().card_size_of(&label(Layout::Planar)),
+        CardSize::STANDARD
+    );
+}
+
+#[test]
+fn images_of_any_resolution_fill_the_slot() {
+    let slot = Rect {
+        x: 10.0,
+        y: 20.0,
+        width: 63.0,
+        height: 88.0,
+    };
+    let options = ProxyOptions {
+        border_extension: BorderExtension {
+            width_mm: 1.5,
+            fit: BorderFit::Overflow,
+        },
+        ..Default::default()
+    };
+    let printed_size =
+        |px: usize, scale: Option<f64>| Mm::from(Px(px).into_pt(IMAGE_DPI)).0 * scale.unwrap();
+
+    // Scryfall png, large, small and an upscaled image
+    for (width, height) in [(745, 1040), (672, 936), (146, 204), (2976, 4156)] {
+        let transform = image_transform(Px(width), Px(height), &slot, &options);
+        assert!((printed_size(width, transform.scale_x) - 66.0).abs() < 1e-9);
+        assert!((printed_size(height, transform.scale_y) - 91.0).abs() < 1e-9);
+        assert_eq!(transform.translate_x, Some(Mm(8.5)));
+        assert_eq!(transform.translate_y, Some(Mm(18.5)));
+    }
+
+    // Landscape images are turned, their width runs along the slot height
+    let transform = image_transform(Px(1040), Px(745), &slot, &options);
+    assert!((printed_size(1040, transform.scale_x) - 91.0).abs() < 1e-9);
+    assert!((printed_size(745, transform.scale_y) - 66.0).abs() < 1e-9);
+    assert_eq!(transform.translate_x, Some(Mm(8.5 + 66.0)));
+}
+
+#[test]
+fn split_cards_are_turned_into_landscape_slots() {
+    let card = |name: &str, card: Card| CardImage {
+        label: CardLabel {
+            name: name.to_string(),
+            set: None,
+            collector_number: None,
+            section: None,
+            card: Some(Arc::new(card)),
+        },
+        back_face: false,
+        image: Ok(Arc::new(Image::from_dynamic_image(
+            &DynamicImage::new_rgb8(745, 1040),
+        ))),
+    };
+    let options = ProxyOptions::default();
+    let cards = vec![
+        card("Fire // Ice", scryfall::fixture(Layout::Split)),
+        card("Lightning Bolt", scryfall::fixture(Layout::Normal)),
+    ];
+
+    // the portrait cards come first, the split card gets a page of landscape slots
+    let sheets = grid_sheets(cards, &None, &options).unwrap();
+    assert_eq!(sheets.len(), 2);
+    let slot = sheets[1].cards[0].slot;
+    assert_eq!(
+        sheets[1].cards[0].label.as_ref().unwrap().name,
+        "Fire // Ice"
+    );
+    assert_eq!((slot.width, slot.height), (CARD_HEIGHT_MM, CARD_WIDTH_MM));
+    assert!(sheets[0].cards[0].slot.height > sheets[0].cards[0].slot.width);
+
+    // the portrait scan is turned to the right, its width runs down the slot
+    let transform = image_transform(Px(745), Px(1040), &slot, &options);
+    let printed_size =
+        |px: usize, scale: Option<f64>| Mm::from(Px(px).into_pt(IMAGE_DPI)).0 * scale.unwrap();
+    assert_eq!(transform.rotate.unwrap().angle_ccw_degrees, -90.0);
+    assert!((printed_size(745, transform.scale_x) - CARD_WIDTH_MM).abs() < 1e-9);
+    assert!((printed_size(1040, transform.scale_y) - CARD_HEIGHT_MM).abs() < 1e-9);
+    assert_eq!(transform.translate_x, Some(Mm(slot.x)));
+    assert_eq!(transform.translate_y, Some(Mm(slot.y + slot.height)));
+}
+
+#[test]
+fn copies_of_double_faced_cards_keep_their_back_face() {
+    let face = |name: &str, back_face| CardImage {
+        label: CardLabel {
+            name: name.to_string(),
+            set: None,
+            collector_number: None,
+            section: None,
+            card: None,
+        },
+        back_face,
+        image: Err(anyhow!("not downloaded")),
+    };
+    let images = vec![
+        face("Delver of Secrets", false),
+        face("Insectile Aberration", true),
+        face("Island", false),
+    ];
+
+    let cards = repeat_copies(images, &[(2, 2), (1, 3)]);
+    let names: Vec<(&str, bool)> = cards
+        .iter()
+        .map(|card| (card.label.name.as_str(), card.back_face))
+        .collect();
+
+    assert_eq!(
+        names,
+        [
+            ("Delver of Secrets", false),
+            ("Insectile Aberration", true),
+            ("Delver of Secrets", false),
+            ("Insectile Aberration", true),
+            ("Island", false),
+            ("Island", false),
+            ("Island", false),
+        ]
+    );
+}
+
+#[test]
+fn decklist_lines_with_names_and_image_paths_are_parsed() {
+    let decklist = "\
+3 Lightning Bolt (M21) 125
+12 Island
+2 art/my alter.png
+1 /home/me/cards/Bolt.JPG
+1 file:///home/me/My%20Cards/bolt.webp
+1 file:///C:/cards/shock.png
+
+Sideboard
+1 Fire // Ice (MH2) 290
+";
+    let entries = parse_decklist(decklist.as_bytes()).unwrap().entries;
+    let summary: Vec<_> = entries
+        .iter()
+        .map(|entry| {
+            (
+                entry.quantity,
+                entry.card_name.as_str(),
+                entry.image_path.as_deref(),
+                entry.section.as_deref(),
+            )
+        })
+        .collect();
+
+    assert_eq!(
+        summary,
+        [
+            (3, "Lightning Bolt", None, None),
+            (12, "Island", None, None),
+            (2, "my alter", Some(Path::new("art/my alter.png")), None),
+            (1, "Bolt", Some(Path::new("/home/me/cards/Bolt.JPG")), None),
+            (
+                1,
+                "bolt",
+                Some(Path::new("/home/me/My Cards/bolt.webp")),
+                None
+            ),
+            (1, "shock", Some(Path::new("C:/cards/shock.png")), None),
+            (1, "Fire // Ice", None, Some("Sideboard")),
+        ]
+    );
+    assert_eq!(entries[0].set_name.as_deref(), Some("M21"));
+    assert_eq!(entries[0].collector_number.as_deref(), Some("125"));
+    assert_eq!(entries[6].collector_number.as_deref(), Some("290"));
+}
+
+#[test]
+fn local_images_come_from_the_decklist_or_the_image_folder() {
+    let image_dir = std::env::temp_dir().join(format!("proxy-images-{}", std::process::id()));
+    fs::create_dir_all(&image_dir).unwrap();
+    for file_name in [
+        "M21-125.png",
+        "Lightning Bolt.JPG",
+        "Fire.webp",
+        "notes.txt",
+    ] {
+        fs::write(image_dir.join(file_name), b"").unwrap();
+    }
+    let local_images = LocalImages::load(&image_dir).unwrap();
+    fs::remove_dir_all(&image_dir).unwrap();
+
+    let entries = parse_decklist(
+        "\
+1 Shock (M21) 125
+4 Lightning Bolt (2XM) 141
+1 Fire // Ice
+1 Island
+1 art/my alter.png
+1 /home/me/cards/bolt.png
+"
+        .as_bytes(),
+    )
+    .unwrap()
+    .entries;
+    let decklist_dir = Path::new("/decks");
+    let paths: Vec<_> = entries
+        .iter()
+        .map(|entry| local_image_path(entry, decklist_dir, Some(&local_images)))
+        .collect();
+
+    assert_eq!(
+        paths,
+        [
+            Some(image_dir.join("M21-125.png")),
+            Some(image_dir.join("Lightning Bolt.JPG")),
+            Some(image_dir.join("Fire.webp")),
+            None,
+            Some(PathBuf::from("/decks/art/my alter.png")),
+            Some(PathBuf::from("/home/me/cards/bolt.png")),
+        ]
+    );
+    assert_eq!(local_image_path(&entries[0], decklist_dir, None), None);
+}
+
+#[test]
+fn image_quality_picks_its_scryfall_version_or_the_best_other_one() {
+    use scryfall::ImageUris;
+
+    let url = |version: &str| Some(format!("https://cards.scryfall.io/{}/front/bolt", version));
+    let all_versions = ImageUris {
+        small: url("small"),
+        normal: url("normal"),
+        large: url("large"),
+        png: url("png"),
+        art_crop: url("art_crop"),
+        border_crop: url("border_crop"),
+    };
+    let card = |image_uris: ImageUris| Card {
+        name: "Lightning Bolt".to_string(),
+        image_uris: Some(image_uris),
+        ..Card::default()
+    };
+    let front = |quality: ImageQuality, card: &Card| quality.card_image_urls(card).unwrap().front;
+
+    let complete = card(all_versions.clone());
+    for (quality, version) in [
+        (ImageQuality::Png, "png"),
+        (ImageQuality::Large, "large"),
+        (ImageQuality::Normal, "normal"),
+        (ImageQuality::BorderCrop, "border_crop"),
+        (ImageQuality::ArtCrop, "art_crop"),
+    ] {
+        assert_eq!(front(quality, &complete), url(version), "{}", quality);
+    }
+
+    // no PNG scan, the large JPEG is the next best
+    let without_png = card(ImageUris {
+        png: None,
+        ..all_versions.clone()
+    });
+    assert_eq!(front(ImageQuality::Png, &without_png), url("large"));
+    assert_eq!(front(ImageQuality::Normal, &without_png), url("normal"));
+
+    let only_normal = card(ImageUris {
+        normal: url("normal"),
+        art_crop: url("art_crop"),
+        ..ImageUris::default()
+    });
+    assert_eq!(front(ImageQuality::BorderCrop, &only_normal), url("normal"));
+
+    let only_art = card(ImageUris {
+        art_crop: url("art_crop"),
+        ..ImageUris::default()
+    });
+    assert!(ImageQuality::Large.card_image_urls(&only_art).is_err());
+}
+
+#[test]
+fn watermark_and_caption_follow_the_printer_correction() {
+    use printpdf::lopdf::{self, content::Content};
+
+    let options = ProxyOptions {
+        watermark: Some(Watermark {
+            rotation: 0.0,
+            ..Watermark::default()
+        }),
+        captions: true,
+        printer_correction: PrinterCorrection {
+            scale_x: 0.9,
+            scale_y: 1.1,
+            offset_x_mm: 2.0,
+            offset_y_mm: -1.0,
+        },
+        ..ProxyOptions::default()
+    };
+    let slot = Rect {
+        x: 20.0,
+        y: 30.0,
+        width: CARD_WIDTH_MM,
+        height: CARD_HEIGHT_MM,
+    };
+    let label = CardLabel {
+        name: "Lightning Bolt".to_string(),
+        set: None,
+        collector_number: None,
+        section: None,
+        card: None,
+    };
+
+    let (doc, page, layer) = PdfDocument::new("Decorations", Mm(PAGE_X), Mm(PAGE_Y), "Cards");
+    let fonts = PageFonts::add_to(&doc, &options).unwrap();
+    fonts.decorate(
+        &doc.get_page(page).get_layer(layer),
+        &options,
+        &label,
+        &slot,
+    );
+    let pdf = lopdf::Document::load_mem(&doc.save_to_bytes().unwrap()).unwrap();
+    let content = pdf.get_page_content(pdf.get_pages()[&1]).unwrap();
+    let operations = Content::decode(&content).unwrap().operations;
+    let operands = |operator: &str| -> Vec<f64> {
+        let operation = operations
+            .iter()
+            .find(|op| op.operator == operator)
+            .unwrap();
+        operation
+            .operands
+            .iter()
+            .filter_map(|operand| operand.as_float().ok())
+            .collect()
+    };
+
+    let card = options.printer_correction.apply_to_rect(&slot);
+    assert!((card.width - CARD_WIDTH_MM * 0.9).abs() < 1e-9);
+    let pt = |mm: f64| Pt::from(Mm(mm)).0;
+
+    // the watermark text is centered horizontally on the corrected card
+    let font_size = operands("Tf")[0];
+    let text_width = font_size * 0.7 * "PROXY".len() as f64;
+    let watermark_x = operands("Tm")[4];
+    assert!((watermark_x + text_width / 2.0 - pt(card.x + card.width / 2.0)).abs() < 0.01);
+
+    // the caption is below the bottom left corner of the corrected card
+    let caption = operands("Td");
+    assert!((caption[0] - pt(card.x)).abs() < 0.01);
+    assert!((caption[1] - pt(card.y - CAPTION_OFFSET_MM)).abs() < 0.01);
+}
+
+// Titles and zero based `/Dest` pages of the outline items below `parent`
+#[cfg(test)]
+fn read_outline(
+    pdf: &printpdf::lopdf::Document,
+    parent: &printpdf::lopdf::Dictionary,
+) -> Vec<OutlineEntry> {
+    use printpdf::lopdf::Object;
+
+    let pages: HashMap<_, _> = pdf
+        .get_pages()
+        .into_iter()
+        .map(|(number, id)| (id, number as usize - 1))
+        .collect();
+    let mut entries = Vec::new();
+    let mut next = parent.get(b"First").ok();
+    while let Some(id) = next {
+        let item = pdf.get_dictionary(id.as_reference().unwrap()).unwrap();
+        let title = match item.get(b"Title").unwrap() {
+            Object::String(bytes, _) if bytes.starts_with(&[0xFE, 0xFF]) => {
+                let units: Vec<u16> = bytes[2..]
+                    .chunks(2)
+                    .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+                    .collect();
+                String::from_utf16(&units).unwrap()
+            }
+            Object::String(bytes, _) => String::from_utf8(bytes.clone()).unwrap(),
+            other => panic!("outline title is {:?}", other),
+        };
+        let dest = item.get(b"Dest").unwrap().as_array().unwrap();
+        let page = pages[&dest[0].as_reference().unwrap()];
+        entries.push(OutlineEntry {
+            children: read_outline(pdf, item),
+            ..OutlineEntry::new(title, page)
+        });
+        next = item.get(b"Next").ok();
+    }
+    entries
+}
+
+#[test]
+fn outline_links_sections_and_cards_to_their_pages() {
+    use printpdf::lopdf;
+
+    let label = |name: &str, section: Option<&str>| CardLabel {
+        name: name.to_string(),
+        set: None,
+        collector_number: None,
+        section: section.map(String::from),
+        card: None,
+    };
+
+    // a cover page, then two pages of cards
+    let (doc, cover_page, cover_layer) =
+        PdfDocument::new("Outline", Mm(PAGE_X), Mm(PAGE_Y), "Checklist");
+    doc.get_page(cover_page).get_layer(cover_layer);
+    let (page, layer) = doc.add_page(Mm(PAGE_X), Mm(PAGE_Y), "Cards");
+    let mut pages = Pages::new(&doc, 1, page, layer);
+    let mut placed_cards = Vec::new();
+    pages.add();
+    placed_cards.push((pages.current(), label("Æther Vial", Some("Main")), false));
+    placed_cards.push((
+        pages.current(),
+        label("Delver of Secrets", Some("Main")),
+        false,
+    ));
+    placed_cards.push((
+        pages.current(),
+        label("Insectile Aberration", Some("Main")),
+        true,
+    ));
+    pages.add();
+    placed_cards.push((
+        pages.current(),
+        label("Jötun Grunt", Some("Sideboard")),
+        false,
+    ));
+
+    let per_card = build_outline(1, &placed_cards, true);
+    let sections = build_outline(1, &placed_cards, false);
+    let without_cover = build_outline(0, &placed_cards[3..], false);
+
+    let pdf_bytes = doc.save_to_bytes().unwrap();
+    let read = |entries: &[OutlineEntry]| {
+        let pdf_bytes = outline::add_outline(pdf_bytes.clone(), entries).unwrap();
+        let pdf = lopdf::Document::load_mem(&pdf_bytes).unwrap();
+        let catalog = pdf.catalog().unwrap();
+        assert_eq!(
+            catalog.get(b"PageMode").unwrap().as_name().unwrap(),
+            b"UseOutlines"
+        );
+        let outlines = pdf
+            .get_dictionary(catalog.get(b"Outlines").unwrap().as_reference().unwrap())
+            .unwrap();
+        read_outline(&pdf, outlines)
+    };
+    let entry = |title: &str, page, children: &[(&str, usize)]| OutlineEntry {
+        children: children
+            .iter()
+            .map(|(title, page)| OutlineEntry::new(*title, *page))
+            .collect(),
+        ..OutlineEntry::new(title, page)
+    };
+
+    // the back face isn't a card of its own
+    assert_eq!(
+        read(&per_card),
+        [
+            entry("Checklist", 0, &[]),
+            entry("Main", 1, &[("Æther Vial", 1), ("Delver of Secrets", 1)]),
+            entry("Sideboard", 2, &[("Jötun Grunt", 2)]),
+        ]
+    );
+    assert_eq!(
+        read(&sections),
+        [
+            entry("Checklist", 0, &[]),
+            entry("Main", 1, &[]),
+            entry("Sideboard", 2, &[]),
+        ]
+    );
+    assert_eq!(read(&without_cover), [entry("Sideboard", 2, &[])]);
+}
+
+#[test]
+fn copies_share_their_image_and_the_pdf_embeds_it_once() {
+    let island = DynamicImage::ImageRgb8(image::RgbImage::from_pixel(
+        63,
+        88,
+        image::Rgb([0, 90, 160]),
+    ));
+    let cards = repeat_copies(
+        vec![CardImage {
+            label: CardLabel {
+                name: "Island".to_string(),
+                set: None,
+                collector_number: None,
+                section: None,
+                card: None,
+            },
+            back_face: false,
+            image: Ok(Arc::new(Image::from_dynamic_image(&island))),
+        }],
+        &[(1, 3)],
+    );
+    let first = cards[0].image.as_ref().unwrap();
+    assert!(cards
+        .iter()
+        .all(|card| Arc::ptr_eq(card.image.as_ref().unwrap(), first)));
+    assert_eq!(Arc::strong_count(first), 3);
+
+    let pdf_path = std::env::temp_dir().join(format!("proxy-copies-{}.pdf", std::process::id()));
+    let deck = Deck {
+        name: "Islands".to_string(),
+        source_file: PathBuf::from("islands.txt"),
+        cards,
+        missing: Vec::new(),
+        card_back: None,
+        color: OutputColor::default(),
+    };
+    create_pdf(&pdf_path, deck, &ProxyOptions::default()).unwrap();
+    let pdf = printpdf::lopdf::Document::load(&pdf_path).unwrap();
+    fs::remove_file(&pdf_path).unwrap();
+
+    let images = pdf
+        .objects
+        .values()
+        .filter_map(|object| object.as_stream().ok())
+        .filter(|stream| {
+            stream
+                .dict
+                .get(b"Subtype")
+                .and_then(|subtype| subtype.as_name())
+                .ok()
+                == Some(b"Image")
+        })
+        .count();
+    assert_eq!(images, 1);
+}
+
+#[test]
+fn single_cards_are_sheets_with_one_card_per_page() {
+    let image = Arc::new(Image::from_dynamic_image(&DynamicImage::new_rgb8(63, 88)));
+    let face = |name: &str, back_face| CardImage {
+        label: CardLabel {
+            name: name.to_string(),
+            set: None,
+            collector_number: None,
+            section: None,
+            card: None,
+        },
+        back_face,
+        image: Ok(Arc::clone(&image)),
+    };
+    let cards = || {
+        vec![
+            face("Delver of Secrets", false),
+            face("Insectile Aberration", true),
+            face("Island", false),
+        ]
+    };
+    let names = |sheets: &[Sheet]| -> Vec<Vec<Option<String>>> {
+        sheets
+            .iter()
+            .map(|sheet| {
+                sheet
+                    .cards
+                    .iter()
+                    .map(|card| card.label.as_ref().map(|label| label.name.clone()))
+                    .collect()
+            })
+            .collect()
+    };
+    let single = ProxyOptions {
+        layout: PageLayout::Single,
+        ..Default::default()
+    };
+
+    // the back face gets its own page
+    let sheets = page_sheets(cards(), &None, &single).unwrap();
+    assert_eq!(
+        names(&sheets),
+        [
+            vec![Some("Delver of Secrets".to_string())],
+            vec![Some("Insectile Aberration".to_string())],
+            vec![Some("Island".to_string())],
+        ]
+    );
+    let slot = GridLayout::single(CardSize::STANDARD).slots()[0];
+    assert!(sheets.iter().all(|sheet| sheet.slots() == [slot]));
+
+    // duplex pages have the back face or the card back behind every card
+    let duplex = Duplex::default();
+    let options = ProxyOptions {
+        duplex: Some(duplex),
+        ..single
+    };
+    let sheets = page_sheets(cards(), &Some(Arc::clone(&image)), &options).unwrap();
+    assert_eq!(
+        names(&sheets),
+        [
+            vec![Some("Delver of Secrets".to_string())],
+            vec![Some("Insectile Aberration".to_string())],
+            vec![Some("Island".to_string())],
+            vec![None],
+        ]
+    );
+    assert_eq!(sheets[3].slots(), [duplex.back_slot(slot)]);
+}
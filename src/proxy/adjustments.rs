@@ -0,0 +1,247 @@
+//! Brightness, contrast, gamma and saturation of the card images. Scryfall scans are made for
+//! screens and often print too dark on inkjet printers, so the adjustments can be saved as a
+//! preset for each printer.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+use image::io::Reader as ImageReader;
+use image::DynamicImage;
+use serde::{Deserialize, Serialize};
+
+use super::CARDBACK_IMAGE;
+
+// Width of the before and after images in the GUI, in pixels
+const PREVIEW_WIDTH: u32 = 220;
+// File in the config folder that holds the presets of all printers
+const PRESETS_FILE_NAME: &str = "adjustment_presets.json";
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ImageAdjustments {
+    /// Added to every channel, from -1 (black) to 1 (white)
+    pub brightness: f64,
+    /// Factor of the distance from middle grey, 1 keeps the contrast
+    pub contrast: f64,
+    /// Values above 1 lighten the midtones without changing black and white
+    pub gamma: f64,
+    /// Factor of the distance from grey, 0 removes all color
+    pub saturation: f64,
+}
+
+impl Default for ImageAdjustments {
+    fn default() -> Self {
+        ImageAdjustments {
+            brightness: 0.0,
+            contrast: 1.0,
+            gamma: 1.0,
+            saturation: 1.0,
+        }
+    }
+}
+
+impl ImageAdjustments {
+    /// Whether the adjustments leave the images as they are
+    pub fn is_neutral(&self) -> bool {
+        *self == ImageAdjustments::default()
+    }
+
+    /// New value of each 8 bit channel value for brightness, contrast and gamma
+    fn tone_curve(&self) -> [u8; 256] {
+        let gamma = self.gamma.max(0.01);
+        std::array::from_fn(|value| {
+            let value = value as f64 / 255.0;
+            let value = ((value - 0.5) * self.contrast + 0.5 + self.brightness).clamp(0.0, 1.0);
+            (value.powf(1.0 / gamma) * 255.0).round() as u8
+        })
+    }
+}
+
+/// Applies the adjustments to the pixels. Greyscale images stay greyscale, other images are
+/// converted to 8 bit RGB(A).
+pub fn adjust_image(image: DynamicImage, adjustments: ImageAdjustments) -> DynamicImage {
+    if adjustments.is_neutral() {
+        return image;
+    }
+    let curve = adjustments.tone_curve();
+
+    match image {
+        DynamicImage::ImageLuma8(mut luma) => {
+            luma.iter_mut()
+                .for_each(|value| *value = curve[*value as usize]);
+            DynamicImage::ImageLuma8(luma)
+        }
+        DynamicImage::ImageLumaA8(mut luma) => {
+            for pixel in luma.pixels_mut() {
+                pixel[0] = curve[pixel[0] as usize];
+            }
+            DynamicImage::ImageLumaA8(luma)
+        }
+        DynamicImage::ImageRgb8(mut rgb) => {
+            for pixel in rgb.pixels_mut() {
+                adjust_rgb(&mut pixel.0, &curve, adjustments.saturation);
+            }
+            DynamicImage::ImageRgb8(rgb)
+        }
+        DynamicImage::ImageRgba8(mut rgba) => {
+            for pixel in rgba.pixels_mut() {
+                let [red, green, blue, alpha] = pixel.0;
+                let mut color = [red, green, blue];
+                adjust_rgb(&mut color, &curve, adjustments.saturation);
+                let [red, green, blue] = color;
+                pixel.0 = [red, green, blue, alpha];
+            }
+            DynamicImage::ImageRgba8(rgba)
+        }
+        other if !other.color().has_color() && !other.color().has_alpha() => {
+            adjust_image(DynamicImage::ImageLuma8(other.to_luma8()), adjustments)
+        }
+        other if other.color().has_alpha() => {
+            adjust_image(DynamicImage::ImageRgba8(other.to_rgba8()), adjustments)
+        }
+        other => adjust_image(DynamicImage::ImageRgb8(other.to_rgb8()), adjustments),
+    }
+}
+
+fn adjust_rgb(pixel: &mut [u8; 3], curve: &[u8; 256], saturation: f64) {
+    let [red, green, blue] = pixel.map(|value| curve[value as usize] as f64);
+    let luma = 0.299 * red + 0.587 * green + 0.114 * blue;
+    *pixel = [red, green, blue].map(|value| {
+        (luma + (value - luma) * saturation)
+            .round()
+            .clamp(0.0, 255.0) as u8
+    });
+}
+
+/// Small version of a card image for the before and after preview, the Magic card back if no
+/// image is given.
+pub fn load_preview_image(image_path: Option<&Path>) -> Result<DynamicImage> {
+    let image = match image_path {
+        Some(path) => image::open(path)
+            .with_context(|| format!("Failed to open preview image '{}'", path.display()))?,
+        None => ImageReader::new(Cursor::new(CARDBACK_IMAGE))
+            .with_guessed_format()
+            .context("Failed to open card back image")?
+            .decode()
+            .context("Failed to decode card back image")?,
+    };
+    Ok(image.thumbnail(PREVIEW_WIDTH, u32::MAX))
+}
+
+/// Saved adjustments by printer name.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct AdjustmentPresets {
+    pub presets: BTreeMap<String, ImageAdjustments>,
+}
+
+impl AdjustmentPresets {
+    /// Reads the presets, a missing file has no presets.
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(AdjustmentPresets::default());
+        }
+        let json = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read presets file '{}'", path.display()))?;
+        serde_json::from_str(&json)
+            .with_context(|| format!("Failed to parse presets file '{}'", path.display()))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)
+                .with_context(|| format!("Failed to create folder '{}'", dir.display()))?;
+        }
+        let json = serde_json::to_string_pretty(self).context("Failed to serialize presets")?;
+        fs::write(path, json)
+            .with_context(|| format!("Failed to write presets file '{}'", path.display()))
+    }
+
+    /// Presets file in the config folder of the user, if the folder is known
+    pub fn default_path() -> Option<PathBuf> {
+        let config_dir = if cfg!(target_os = "windows") {
+            std::env::var_os("APPDATA").map(PathBuf::from)
+        } else if cfg!(target_os = "macos") {
+            std::env::var_os("HOME")
+                .map(|home| PathBuf::from(home).join("Library/Application Support"))
+        } else {
+            std::env::var_os("XDG_CONFIG_HOME")
+                .map(PathBuf::from)
+                .or_else(|| {
+                    std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config"))
+                })
+        }?;
+        Some(config_dir.join("mtg-proxy-creator").join(PRESETS_FILE_NAME))
+    }
+}
+
+#[test]
+fn gamma_and_brightness_lighten_dark_scans() {
+    let scan = DynamicImage::ImageRgb8(image::RgbImage::from_pixel(4, 4, image::Rgb([64, 64, 64])));
+    assert_eq!(
+        adjust_image(scan.clone(), ImageAdjustments::default()),
+        scan
+    );
+
+    let gamma = ImageAdjustments {
+        gamma: 2.0,
+        ..ImageAdjustments::default()
+    };
+    let curve = gamma.tone_curve();
+    assert_eq!((curve[0], curve[64], curve[255]), (0, 128, 255));
+
+    let brighter = ImageAdjustments {
+        brightness: 0.2,
+        ..ImageAdjustments::default()
+    };
+    let adjusted = adjust_image(scan, brighter).to_rgb8();
+    assert_eq!(adjusted.get_pixel(0, 0).0, [115, 115, 115]);
+}
+
+#[test]
+fn saturation_keeps_grey_and_alpha() {
+    let desaturate = ImageAdjustments {
+        saturation: 0.0,
+        ..ImageAdjustments::default()
+    };
+    let red = DynamicImage::ImageRgba8(image::RgbaImage::from_pixel(
+        2,
+        2,
+        image::Rgba([200, 0, 0, 10]),
+    ));
+    let adjusted = adjust_image(red, desaturate).to_rgba8();
+    assert_eq!(adjusted.get_pixel(0, 0).0, [60, 60, 60, 10]);
+
+    let grey = DynamicImage::ImageLuma8(image::GrayImage::from_pixel(2, 2, image::Luma([100])));
+    assert_eq!(adjust_image(grey.clone(), desaturate), grey);
+}
+
+#[test]
+fn presets_are_saved_per_printer() {
+    let path = std::env::temp_dir()
+        .join(format!("proxy-presets-{}", std::process::id()))
+        .join(PRESETS_FILE_NAME);
+    assert_eq!(
+        AdjustmentPresets::load(&path).unwrap(),
+        AdjustmentPresets::default()
+    );
+
+    let mut presets = AdjustmentPresets::default();
+    let inkjet = ImageAdjustments {
+        brightness: 0.05,
+        gamma: 1.3,
+        ..ImageAdjustments::default()
+    };
+    presets.presets.insert("Office inkjet".to_string(), inkjet);
+    presets
+        .presets
+        .insert("Laser".to_string(), ImageAdjustments::default());
+    presets.save(&path).unwrap();
+
+    let loaded = AdjustmentPresets::load(&path).unwrap();
+    fs::remove_dir_all(path.parent().unwrap()).unwrap();
+    assert_eq!(loaded, presets);
+    assert_eq!(loaded.presets["Office inkjet"], inkjet);
+}
@@ -0,0 +1,329 @@
+//! Printer calibration page and the scale and offset correction measured with it.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use printpdf::{BuiltinFont, IndirectFontRef, Line, Mm, PdfDocument, PdfLayerReference, Point};
+
+use super::layout::Rect;
+use super::shared_images::SharedImages;
+use super::{
+    save_pdf, Duplex, OutputColor, ProxyOptions, APP_USER_AGENT, CARD_HEIGHT_MM, CARD_WIDTH_MM,
+    PAGE_X, PAGE_Y,
+};
+
+// Distance of the rulers from the page edge, most printers can't print closer
+const RULER_MARGIN_MM: f64 = 10.0;
+const CROSSHAIR_SIZE_MM: f64 = 5.0;
+const LABEL_FONT_SIZE: f64 = 6.0;
+const TEXT_FONT_SIZE: f64 = 9.0;
+// Positions of the crosshairs, measured from the bottom left page corner
+const CROSSHAIRS: [(f64, f64); 5] = [
+    (30.0, 30.0),
+    (180.0, 30.0),
+    (PAGE_X / 2.0, PAGE_Y / 2.0),
+    (30.0, 267.0),
+    (180.0, 267.0),
+];
+
+/// Scale and offset applied to every card placement, so printed cards come out at their
+/// real size and position.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PrinterCorrection {
+    /// Horizontal scale around the page center, 1.0 prints unchanged
+    pub scale_x: f64,
+    /// Vertical scale around the page center
+    pub scale_y: f64,
+    /// Shift to the right in mm
+    pub offset_x_mm: f64,
+    /// Shift upwards in mm
+    pub offset_y_mm: f64,
+}
+
+impl Default for PrinterCorrection {
+    fn default() -> Self {
+        PrinterCorrection {
+            scale_x: 1.0,
+            scale_y: 1.0,
+            offset_x_mm: 0.0,
+            offset_y_mm: 0.0,
+        }
+    }
+}
+
+impl PrinterCorrection {
+    /// Corrected position of the point at `x`, `y` on the page.
+    pub(super) fn apply(&self, x: f64, y: f64) -> (f64, f64) {
+        (
+            PAGE_X / 2.0 + (x - PAGE_X / 2.0) * self.scale_x + self.offset_x_mm,
+            PAGE_Y / 2.0 + (y - PAGE_Y / 2.0) * self.scale_y + self.offset_y_mm,
+        )
+    }
+
+    /// Corrected position and size of a rectangle on the page.
+    pub(super) fn apply_to_rect(&self, rect: &Rect) -> Rect {
+        let (x, y) = self.apply(rect.x, rect.y);
+        Rect {
+            x,
+            y,
+            width: rect.width * self.scale_x,
+            height: rect.height * self.scale_y,
+        }
+    }
+}
+
+/// Creates a PDF with rulers, a card outline and crosshairs for measuring the printer,
+/// followed by a back page with duplex alignment marks. The printer correction and duplex
+/// offset of `options` are applied, so a calibrated printer prints it at exact size.
+pub fn create_calibration_page(pdf_path: &Path, options: &ProxyOptions) -> Result<PathBuf> {
+    let (doc, page, layer) =
+        PdfDocument::new("Printer calibration", Mm(PAGE_X), Mm(PAGE_Y), "Calibration");
+    let doc = doc.with_creator(APP_USER_AGENT);
+    let font = doc.add_builtin_font(BuiltinFont::Helvetica)?;
+    let duplex = options.duplex.unwrap_or_default();
+
+    let front = Sheet {
+        layer: doc.get_page(page).get_layer(layer),
+        font: font.clone(),
+        correction: options.printer_correction,
+    };
+    front.rulers();
+
+    let card_x = (PAGE_X - CARD_WIDTH_MM) / 2.0;
+    let card_y = (PAGE_Y - CARD_HEIGHT_MM) / 2.0;
+    front.rectangle(card_x, card_y, CARD_WIDTH_MM, CARD_HEIGHT_MM);
+    front.text(
+        &format!("{} x {} mm", CARD_WIDTH_MM, CARD_HEIGHT_MM),
+        TEXT_FONT_SIZE,
+        card_x + 2.0,
+        card_y + CARD_HEIGHT_MM - 5.0,
+    );
+    for (x, y) in CROSSHAIRS {
+        front.crosshair(x, y);
+        front.text(
+            &format!("{}, {} mm", x, y),
+            LABEL_FONT_SIZE,
+            x + 1.5,
+            y + 1.5,
+        );
+    }
+
+    let instructions = [
+        "Print at 100 % / actual size without fit to page.",
+        "Measure the card outline and multiply the current scale by 63 divided by",
+        "the measured width and by 88 divided by the measured height.",
+        "The crosshairs are measured from the bottom left page corner,",
+        "a difference to the printed position is added to the current offset.",
+    ];
+    for (i, line) in instructions.iter().enumerate() {
+        front.text(line, TEXT_FONT_SIZE, 40.0, 80.0 - i as f64 * 4.5);
+    }
+
+    let (page, layer) = doc.add_page(Mm(PAGE_X), Mm(PAGE_Y), "Calibration");
+    let back = Sheet {
+        layer: doc.get_page(page).get_layer(layer),
+        font,
+        correction: options.printer_correction,
+    };
+    back_page_marks(&back, &duplex);
+
+    save_pdf(
+        pdf_path,
+        doc,
+        SharedImages::default(),
+        &[],
+        &OutputColor::default(),
+        None,
+    )
+}
+
+// Crosshairs behind the front crosshairs, they line up with them when the printed
+// sheet is held against the light if the duplex offset is right.
+fn back_page_marks(back: &Sheet, duplex: &Duplex) {
+    for (x, y) in CROSSHAIRS {
+        let mark = duplex.back_slot(Rect {
+            x,
+            y,
+            width: 0.0,
+            height: 0.0,
+        });
+        back.crosshair(mark.x, mark.y);
+    }
+    back.text(
+        &format!("Duplex back page, {}", duplex.flip),
+        TEXT_FONT_SIZE,
+        40.0,
+        80.0,
+    );
+    back.text(
+        "The crosshairs should be behind the crosshairs of the front page.",
+        TEXT_FONT_SIZE,
+        40.0,
+        75.5,
+    );
+}
+
+/// A page of the calibration PDF, with all coordinates in mm before printer correction.
+struct Sheet {
+    layer: PdfLayerReference,
+    font: IndirectFontRef,
+    correction: PrinterCorrection,
+}
+
+impl Sheet {
+    /// Rulers along the top and left edge, labeled with the distance from the left and
+    /// bottom page edge.
+    fn rulers(&self) {
+        let top = PAGE_Y - RULER_MARGIN_MM;
+        let left = RULER_MARGIN_MM;
+
+        for mm in (RULER_MARGIN_MM as i64)..=((PAGE_X - RULER_MARGIN_MM) as i64) {
+            let x = mm as f64;
+            self.line((x, top), (x, top - tick_length(mm)));
+            if mm % 10 == 0 {
+                self.text(&mm.to_string(), LABEL_FONT_SIZE, x + 0.5, top - 6.0);
+            }
+        }
+        for mm in (RULER_MARGIN_MM as i64)..=(top as i64) {
+            let y = mm as f64;
+            self.line((left, y), (left + tick_length(mm), y));
+            if mm % 10 == 0 {
+                self.text(&mm.to_string(), LABEL_FONT_SIZE, left + 5.5, y - 1.0);
+            }
+        }
+    }
+
+    fn crosshair(&self, x: f64, y: f64) {
+        let half = CROSSHAIR_SIZE_MM / 2.0;
+        self.line((x - half, y), (x + half, y));
+        self.line((x, y - half), (x, y + half));
+    }
+
+    fn rectangle(&self, x: f64, y: f64, width: f64, height: f64) {
+        let corners = [
+            (x, y),
+            (x + width, y),
+            (x + width, y + height),
+            (x, y + height),
+        ];
+        self.shape(&corners, true);
+    }
+
+    fn line(&self, from: (f64, f64), to: (f64, f64)) {
+        self.shape(&[from, to], false);
+    }
+
+    fn shape(&self, points: &[(f64, f64)], is_closed: bool) {
+        self.layer.set_outline_thickness(0.3);
+        self.layer.add_shape(Line {
+            points: points
+                .iter()
+                .map(|&(x, y)| {
+                    let (x, y) = self.correction.apply(x, y);
+                    (Point::new(Mm(x), Mm(y)), false)
+                })
+                .collect(),
+            is_closed,
+            has_fill: false,
+            has_stroke: true,
+            is_clipping_path: false,
+        });
+    }
+
+    fn text(&self, text: &str, font_size: f64, x: f64, y: f64) {
+        let (x, y) = self.correction.apply(x, y);
+        self.layer
+            .use_text(text, font_size, Mm(x), Mm(y), &self.font);
+    }
+}
+
+// Ruler ticks are longer every 5 and 10 mm
+fn tick_length(mm: i64) -> f64 {
+    match mm {
+        mm if mm % 10 == 0 => 5.0,
+        mm if mm % 5 == 0 => 3.0,
+        _ => 1.5,
+    }
+}
+
+#[test]
+fn no_correction_keeps_every_point() {
+    let identity = PrinterCorrection::default();
+    for (x, y) in [(0.0, 0.0), (10.5, 192.5), (PAGE_X, PAGE_Y)] {
+        assert_eq!(identity.apply(x, y), (x, y));
+    }
+    let card = Rect {
+        x: 73.5,
+        y: 104.5,
+        width: CARD_WIDTH_MM,
+        height: CARD_HEIGHT_MM,
+    };
+    assert_eq!(identity.apply_to_rect(&card), card);
+}
+
+#[test]
+fn scale_is_around_the_page_center() {
+    let correction = PrinterCorrection {
+        scale_x: 0.98,
+        scale_y: 1.02,
+        ..PrinterCorrection::default()
+    };
+    let close = |(x, y): (f64, f64), (expected_x, expected_y): (f64, f64)| {
+        (x - expected_x).abs() < 1e-9 && (y - expected_y).abs() < 1e-9
+    };
+
+    let center = (PAGE_X / 2.0, PAGE_Y / 2.0);
+    assert!(close(correction.apply(center.0, center.1), center));
+    // the page origin moves towards the center on the narrower axis and away on the wider
+    assert!(close(correction.apply(0.0, 0.0), (2.1, -2.97)));
+    assert!(close(
+        correction.apply(PAGE_X, PAGE_Y),
+        (PAGE_X - 2.1, PAGE_Y + 2.97)
+    ));
+
+    let card = correction.apply_to_rect(&Rect {
+        x: 0.0,
+        y: 0.0,
+        width: CARD_WIDTH_MM,
+        height: CARD_HEIGHT_MM,
+    });
+    assert!(close((card.x, card.y), (2.1, -2.97)));
+    assert!(close(
+        (card.width, card.height),
+        (CARD_WIDTH_MM * 0.98, CARD_HEIGHT_MM * 1.02)
+    ));
+}
+
+#[test]
+fn offset_is_added_after_scaling() {
+    let correction = PrinterCorrection {
+        scale_x: 0.98,
+        scale_y: 1.02,
+        offset_x_mm: 1.5,
+        offset_y_mm: -2.0,
+    };
+    let scaled_only = PrinterCorrection {
+        offset_x_mm: 0.0,
+        offset_y_mm: 0.0,
+        ..correction
+    };
+    for (x, y) in [(0.0, 0.0), (10.5, 192.5), (PAGE_X, PAGE_Y)] {
+        let (scaled_x, scaled_y) = scaled_only.apply(x, y);
+        let (corrected_x, corrected_y) = correction.apply(x, y);
+        assert!((corrected_x - (scaled_x + 1.5)).abs() < 1e-9);
+        assert!((corrected_y - (scaled_y - 2.0)).abs() < 1e-9);
+    }
+    // sizes only scale, the offset moves the card without resizing it
+    let card = Rect {
+        x: 10.5,
+        y: 192.5,
+        width: CARD_WIDTH_MM,
+        height: CARD_HEIGHT_MM,
+    };
+    let corrected = correction.apply_to_rect(&card);
+    let scaled = scaled_only.apply_to_rect(&card);
+    assert_eq!(
+        (corrected.width, corrected.height),
+        (scaled.width, scaled.height)
+    );
+}
@@ -0,0 +1,622 @@
+//! Color spaces for commercial printers. The card images are untagged DeviceRGB by default,
+//! they can be tagged as sRGB with an output intent or converted to CMYK with the ICC profile
+//! of the print shop, which then becomes the output intent.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use printpdf::lopdf::{self, Dictionary, Object, StringFormat};
+use printpdf::{ColorSpace, ImageXObject};
+
+const SRGB_DESCRIPTION: &str = "sRGB IEC61966-2.1";
+// Entries of the generated sRGB tone curve
+const SRGB_CURVE_POINTS: usize = 1024;
+// D50, the white point of the ICC profile connection space
+const D50: [f64; 3] = [0.9642, 1.0, 0.8249];
+// sRGB primaries adapted to D50, as in the sRGB ICC profiles, one row per X, Y and Z
+const SRGB_TO_XYZ: [[f64; 3]; 3] = [
+    [0.436_074_7, 0.385_064_9, 0.143_080_4],
+    [0.222_504_5, 0.716_878_6, 0.060_616_9],
+    [0.013_932_2, 0.097_104_5, 0.714_173_3],
+];
+const ICC_HEADER_SIZE: usize = 128;
+// Grid points per channel of the RGB to CMYK table computed from the profile
+const RGB_GRID_POINTS: usize = 33;
+
+/// Color space the card images are written in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorOutput {
+    /// DeviceRGB without a profile, the printer decides what the colors mean
+    #[default]
+    DeviceRgb,
+    /// RGB images with an sRGB output intent
+    Srgb,
+    /// CMYK images converted with an ICC profile, which is added as output intent
+    Cmyk,
+}
+
+impl ColorOutput {
+    pub const ALL: [ColorOutput; 3] =
+        [ColorOutput::DeviceRgb, ColorOutput::Srgb, ColorOutput::Cmyk];
+}
+
+impl std::fmt::Display for ColorOutput {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let label = match self {
+            ColorOutput::DeviceRgb => "RGB (untagged)",
+            ColorOutput::Srgb => "RGB with sRGB output intent",
+            ColorOutput::Cmyk => "CMYK with ICC profile",
+        };
+        f.write_str(label)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ColorSettings {
+    pub output: ColorOutput,
+    /// CMYK ICC profile of the print shop, e.g. Coated FOGRA39
+    pub cmyk_profile: Option<PathBuf>,
+}
+
+/// Color conversion and output intent of a run, with the CMYK profile loaded once
+#[derive(Debug, Default)]
+pub(super) enum OutputColor {
+    #[default]
+    DeviceRgb,
+    Srgb,
+    Cmyk(CmykProfile),
+}
+
+impl OutputColor {
+    pub(super) fn load(settings: &ColorSettings) -> Result<Self> {
+        Ok(match (settings.output, &settings.cmyk_profile) {
+            (ColorOutput::DeviceRgb, _) => OutputColor::DeviceRgb,
+            (ColorOutput::Srgb, _) => OutputColor::Srgb,
+            (ColorOutput::Cmyk, Some(path)) => OutputColor::Cmyk(CmykProfile::load(path)?),
+            (ColorOutput::Cmyk, None) => {
+                bail!("Please select the CMYK ICC profile of your print shop.")
+            }
+        })
+    }
+
+    /// Converts RGB images to CMYK in the CMYK mode. Greyscale images stay greyscale.
+    pub(super) fn convert(&self, image_x_object: ImageXObject) -> ImageXObject {
+        let OutputColor::Cmyk(profile) = self else {
+            return image_x_object;
+        };
+        if !matches!(image_x_object.color_space, ColorSpace::Rgb) {
+            return image_x_object;
+        }
+
+        let image_data = image_x_object
+            .image_data
+            .chunks_exact(3)
+            .flat_map(|rgb| profile.rgb_to_cmyk([rgb[0], rgb[1], rgb[2]]))
+            .collect();
+        ImageXObject {
+            color_space: ColorSpace::Cmyk,
+            image_data,
+            ..image_x_object
+        }
+    }
+
+    /// Adds the profile of the color space as output intent of the PDF.
+    pub(super) fn add_output_intent(&self, pdf_bytes: Vec<u8>) -> Result<Vec<u8>> {
+        let (icc, components, description) = match self {
+            OutputColor::DeviceRgb => return Ok(pdf_bytes),
+            OutputColor::Srgb => (srgb_profile(), 3, SRGB_DESCRIPTION),
+            OutputColor::Cmyk(profile) => (profile.icc.clone(), 4, profile.description.as_str()),
+        };
+
+        let mut doc =
+            lopdf::Document::load_mem(&pdf_bytes).context("Failed to read created PDF")?;
+        let mut profile_dict = Dictionary::new();
+        profile_dict.set("N", Object::Integer(components));
+        let profile_id = doc.add_object(lopdf::Stream::new(profile_dict, icc));
+
+        let text = |text: &str| Object::String(text.as_bytes().to_vec(), StringFormat::Literal);
+        let mut output_intent = Dictionary::new();
+        output_intent.set("Type", Object::Name(b"OutputIntent".to_vec()));
+        output_intent.set("S", Object::Name(b"GTS_PDFX".to_vec()));
+        output_intent.set("OutputConditionIdentifier", text(description));
+        output_intent.set("Info", text(description));
+        output_intent.set("RegistryName", text("http://www.color.org"));
+        output_intent.set("DestinationOutputProfile", Object::Reference(profile_id));
+
+        let catalog_id = doc
+            .trailer
+            .get(b"Root")
+            .and_then(Object::as_reference)
+            .context("PDF has no catalog")?;
+        doc.get_object_mut(catalog_id)
+            .and_then(Object::as_dict_mut)
+            .context("PDF has no catalog")?
+            .set(
+                "OutputIntents",
+                Object::Array(vec![Object::Dictionary(output_intent)]),
+            );
+
+        let mut pdf_bytes = Vec::new();
+        doc.save_to(&mut pdf_bytes)
+            .context("Failed to write output intent")?;
+        Ok(pdf_bytes)
+    }
+}
+
+/// CMYK output profile with the lookup table from the profile connection space to CMYK.
+#[derive(Debug)]
+pub(super) struct CmykProfile {
+    /// The profile file, embedded as output intent
+    icc: Vec<u8>,
+    description: String,
+    /// CMYK values on a grid over the RGB cube, looking up the profile for every pixel is slow
+    rgb_grid: Vec<[f64; 4]>,
+}
+
+impl CmykProfile {
+    fn load(path: &Path) -> Result<Self> {
+        let icc = fs::read(path)
+            .with_context(|| format!("Failed to read ICC profile '{}'", path.display()))?;
+        let name = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        CmykProfile::parse(icc, name)
+            .with_context(|| format!("Unsupported ICC profile '{}'", path.display()))
+    }
+
+    fn parse(icc: Vec<u8>, name: String) -> Result<Self> {
+        if icc.len() < ICC_HEADER_SIZE + 4 || &icc[36..40] != b"acsp" {
+            bail!("Not an ICC profile");
+        }
+        if &icc[16..20] != b"CMYK" {
+            bail!("Not a CMYK profile");
+        }
+        let lab_connection = match &icc[20..24] {
+            b"Lab " => true,
+            b"XYZ " => false,
+            other => bail!(
+                "Unknown profile connection space '{}'",
+                String::from_utf8_lossy(other)
+            ),
+        };
+
+        // perceptual rendering, relative colorimetric if the profile has no perceptual table
+        let lut = match tag(&icc, b"B2A0").or_else(|| tag(&icc, b"B2A1")) {
+            Some(data) => Lut::parse(data)?,
+            None => bail!("The profile has no table to convert colors to CMYK"),
+        };
+        let description = tag(&icc, b"desc")
+            .and_then(ascii_description)
+            .unwrap_or(name);
+
+        let last = (RGB_GRID_POINTS - 1) as f64;
+        let rgb_grid = (0..RGB_GRID_POINTS.pow(3))
+            .map(|i| {
+                let rgb = [
+                    i / (RGB_GRID_POINTS * RGB_GRID_POINTS),
+                    i / RGB_GRID_POINTS % RGB_GRID_POINTS,
+                    i % RGB_GRID_POINTS,
+                ];
+                let linear = rgb.map(|value| srgb_to_linear(value as f64 / last));
+                let xyz =
+                    SRGB_TO_XYZ.map(|row| row.iter().zip(linear).map(|(m, c)| m * c).sum::<f64>());
+                let input = if lab_connection {
+                    let [l, a, b] = xyz_to_lab(xyz);
+                    lut.encode_lab(l, a, b)
+                } else {
+                    // XYZ is encoded with 1.0 at 0x8000
+                    lut.apply_matrix(xyz.map(|value| value * 32768.0 / 65535.0))
+                };
+                lut.apply(input)
+            })
+            .collect();
+
+        Ok(CmykProfile {
+            icc,
+            description,
+            rgb_grid,
+        })
+    }
+
+    fn rgb_to_cmyk(&self, rgb: [u8; 3]) -> [u8; 4] {
+        let position = rgb.map(|value| value as f64 / 255.0);
+        trilinear(&self.rgb_grid, RGB_GRID_POINTS, position)
+            .map(|value| (value * 255.0).round().clamp(0.0, 255.0) as u8)
+    }
+}
+
+/// An ICC lut8 (`mft1`) or lut16 (`mft2`) table from the profile connection space to CMYK,
+/// with all values scaled to 0..=1
+#[derive(Debug)]
+struct Lut {
+    /// Applied to XYZ input before the input tables, profiles with Lab input have the identity
+    matrix: [[f64; 3]; 3],
+    grid_points: usize,
+    /// lut16 tables encode L* 100 as 0xFF00 instead of 0xFFFF
+    sixteen_bit: bool,
+    input_tables: Vec<Vec<f64>>,
+    clut: Vec<[f64; 4]>,
+    output_tables: Vec<Vec<f64>>,
+}
+
+impl Lut {
+    fn parse(data: &[u8]) -> Result<Self> {
+        let sixteen_bit = match data.get(0..4) {
+            Some(b"mft2") => true,
+            Some(b"mft1") => false,
+            _ => bail!("Only lut8 and lut16 tables are supported"),
+        };
+        if data.len() < 52 {
+            bail!("Truncated lookup table");
+        }
+        // s15Fixed16 numbers, row by row
+        let matrix = [0, 1, 2].map(|row| {
+            [0, 1, 2]
+                .map(|column| u32_at(data, 12 + 4 * (3 * row + column)) as i32 as f64 / 65536.0)
+        });
+        let input_channels = data[8] as usize;
+        let output_channels = data[9] as usize;
+        let grid_points = data[10] as usize;
+        let (input_entries, output_entries, mut offset) = if sixteen_bit {
+            (u16_at(data, 48) as usize, u16_at(data, 50) as usize, 52)
+        } else {
+            (256, 256, 48)
+        };
+        let value_size = if sixteen_bit { 2 } else { 1 };
+        let clut_size = grid_points.pow(input_channels as u32) * output_channels;
+        let table_size =
+            input_channels * input_entries + clut_size + output_channels * output_entries;
+        if grid_points < 2 || data.len() < offset + table_size * value_size {
+            bail!("Truncated lookup table");
+        }
+        if input_channels != 3 || output_channels != 4 {
+            bail!("The profile's table doesn't convert to CMYK");
+        }
+
+        let mut read = |count: usize| -> Vec<f64> {
+            let values = (0..count)
+                .map(|i| match sixteen_bit {
+                    true => u16_at(data, offset + 2 * i) as f64 / 65535.0,
+                    false => data[offset + i] as f64 / 255.0,
+                })
+                .collect();
+            offset += count * value_size;
+            values
+        };
+        let input_tables = (0..input_channels).map(|_| read(input_entries)).collect();
+        let clut = read(clut_size)
+            .chunks_exact(4)
+            .map(|cmyk| [cmyk[0], cmyk[1], cmyk[2], cmyk[3]])
+            .collect();
+        let output_tables = (0..output_channels).map(|_| read(output_entries)).collect();
+
+        Ok(Lut {
+            matrix,
+            grid_points,
+            sixteen_bit,
+            input_tables,
+            clut,
+            output_tables,
+        })
+    }
+
+    fn encode_lab(&self, l: f64, a: f64, b: f64) -> [f64; 3] {
+        if self.sixteen_bit {
+            [
+                l / 100.0 * 65280.0 / 65535.0,
+                (a + 128.0) * 256.0 / 65535.0,
+                (b + 128.0) * 256.0 / 65535.0,
+            ]
+        } else {
+            [l / 100.0, (a + 128.0) / 255.0, (b + 128.0) / 255.0]
+        }
+    }
+
+    fn apply_matrix(&self, xyz: [f64; 3]) -> [f64; 3] {
+        self.matrix
+            .map(|row| row.iter().zip(xyz).map(|(m, value)| m * value).sum())
+    }
+
+    /// Input curves, interpolation in the grid and output curves
+    fn apply(&self, input: [f64; 3]) -> [f64; 4] {
+        let position =
+            [0, 1, 2].map(|channel| interpolate(&self.input_tables[channel], input[channel]));
+        let cmyk = trilinear(&self.clut, self.grid_points, position);
+        [0, 1, 2, 3].map(|channel| interpolate(&self.output_tables[channel], cmyk[channel]))
+    }
+}
+
+/// Trilinear interpolation in a grid of CMYK values over a cube from 0 to 1, the first
+/// channel varies slowest.
+fn trilinear(grid: &[[f64; 4]], grid_points: usize, position: [f64; 3]) -> [f64; 4] {
+    let last = (grid_points - 1) as f64;
+    let cells = position.map(|value| {
+        let position = value.clamp(0.0, 1.0) * last;
+        let lower = (position.floor() as usize).min(grid_points - 2);
+        (lower, position - lower as f64)
+    });
+
+    let mut output = [0.0; 4];
+    for corner in 0..8 {
+        let mut index = 0;
+        let mut weight = 1.0;
+        for (channel, &(lower, fraction)) in cells.iter().enumerate() {
+            let upper_corner = (corner >> (2 - channel)) & 1 == 1;
+            index = index * grid_points + lower + upper_corner as usize;
+            weight *= if upper_corner {
+                fraction
+            } else {
+                1.0 - fraction
+            };
+        }
+        for (out, value) in output.iter_mut().zip(grid[index]) {
+            *out += weight * value;
+        }
+    }
+    output
+}
+
+/// Linear interpolation in a table that spans 0..=1
+fn interpolate(table: &[f64], value: f64) -> f64 {
+    let position = value.clamp(0.0, 1.0) * (table.len() - 1) as f64;
+    let lower = (position.floor() as usize).min(table.len().saturating_sub(2));
+    let fraction = position - lower as f64;
+    match table.get(lower + 1) {
+        Some(upper) => table[lower] * (1.0 - fraction) + upper * fraction,
+        None => table[lower],
+    }
+}
+
+fn srgb_to_linear(value: f64) -> f64 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn xyz_to_lab(xyz: [f64; 3]) -> [f64; 3] {
+    let f = |t: f64| {
+        let delta: f64 = 6.0 / 29.0;
+        if t > delta.powi(3) {
+            t.cbrt()
+        } else {
+            t / (3.0 * delta * delta) + 4.0 / 29.0
+        }
+    };
+    let [x, y, z] = [0, 1, 2].map(|i| f(xyz[i] / D50[i]));
+    [116.0 * y - 16.0, 500.0 * (x - y), 200.0 * (y - z)]
+}
+
+fn u16_at(data: &[u8], offset: usize) -> u16 {
+    u16::from_be_bytes([data[offset], data[offset + 1]])
+}
+
+fn u32_at(data: &[u8], offset: usize) -> u32 {
+    u32::from_be_bytes(data[offset..offset + 4].try_into().unwrap())
+}
+
+/// Data of a tag from the tag table of an ICC profile
+fn tag<'a>(icc: &'a [u8], signature: &[u8; 4]) -> Option<&'a [u8]> {
+    let count = u32_at(icc, ICC_HEADER_SIZE) as usize;
+    (0..count)
+        .map(|i| ICC_HEADER_SIZE + 4 + 12 * i)
+        .take_while(|entry| entry + 12 <= icc.len())
+        .find(|&entry| &icc[entry..entry + 4] == signature)
+        .and_then(|entry| {
+            let offset = u32_at(icc, entry + 4) as usize;
+            let size = u32_at(icc, entry + 8) as usize;
+            icc.get(offset..offset.checked_add(size)?)
+        })
+}
+
+// ASCII part of a version 2 `desc` tag
+fn ascii_description(data: &[u8]) -> Option<String> {
+    if data.get(0..4)? != b"desc" {
+        return None;
+    }
+    let length = u32::from_be_bytes(data.get(8..12)?.try_into().ok()?) as usize;
+    let text = data.get(12..12usize.checked_add(length)?)?;
+    let text = String::from_utf8_lossy(text)
+        .trim_end_matches('\0')
+        .trim()
+        .to_string();
+    (!text.is_empty()).then_some(text)
+}
+
+/// Version 2 ICC profile of sRGB, built from the primaries and tone curve of the standard
+pub(super) fn srgb_profile() -> Vec<u8> {
+    let xyz = |[x, y, z]: [f64; 3]| {
+        let mut data = b"XYZ \0\0\0\0".to_vec();
+        for value in [x, y, z] {
+            data.extend(((value * 65536.0).round() as i32).to_be_bytes());
+        }
+        data
+    };
+    let column = |i: usize| SRGB_TO_XYZ.map(|row| row[i]);
+
+    let mut description = b"desc\0\0\0\0".to_vec();
+    description.extend((SRGB_DESCRIPTION.len() as u32 + 1).to_be_bytes());
+    description.extend(SRGB_DESCRIPTION.as_bytes());
+    // terminating zero, empty Unicode and ScriptCode descriptions
+    description.extend([0; 1 + 4 + 4 + 2 + 1 + 67]);
+
+    let mut curve = b"curv\0\0\0\0".to_vec();
+    curve.extend((SRGB_CURVE_POINTS as u32).to_be_bytes());
+    for i in 0..SRGB_CURVE_POINTS {
+        let value = srgb_to_linear(i as f64 / (SRGB_CURVE_POINTS - 1) as f64);
+        curve.extend(((value * 65535.0).round() as u16).to_be_bytes());
+    }
+
+    let tags: [(&[u8; 4], Vec<u8>); 9] = [
+        (b"desc", description),
+        (b"cprt", b"text\0\0\0\0No copyright, use freely\0".to_vec()),
+        (b"wtpt", xyz(D50)),
+        (b"rXYZ", xyz(column(0))),
+        (b"gXYZ", xyz(column(1))),
+        (b"bXYZ", xyz(column(2))),
+        (b"rTRC", curve.clone()),
+        (b"gTRC", curve.clone()),
+        (b"bTRC", curve),
+    ];
+
+    let mut table = (tags.len() as u32).to_be_bytes().to_vec();
+    let mut data = Vec::new();
+    let data_start = ICC_HEADER_SIZE + 4 + 12 * tags.len();
+    for (signature, tag_data) in &tags {
+        table.extend(*signature);
+        table.extend(((data_start + data.len()) as u32).to_be_bytes());
+        table.extend((tag_data.len() as u32).to_be_bytes());
+        data.extend(tag_data);
+        // tags start at multiples of four bytes
+        data.resize(data.len().next_multiple_of(4), 0);
+    }
+
+    let mut icc = vec![0; ICC_HEADER_SIZE];
+    let size = (ICC_HEADER_SIZE + table.len() + data.len()) as u32;
+    icc[0..4].copy_from_slice(&size.to_be_bytes());
+    icc[8..12].copy_from_slice(&[2, 0x10, 0, 0]);
+    icc[12..16].copy_from_slice(b"mntr");
+    icc[16..20].copy_from_slice(b"RGB ");
+    icc[20..24].copy_from_slice(b"XYZ ");
+    icc[36..40].copy_from_slice(b"acsp");
+    icc[68..80].copy_from_slice(&xyz(D50)[8..]);
+    icc.extend(table);
+    icc.extend(data);
+    icc
+}
+
+#[cfg(test)]
+fn grey_ramp_cmyk_profile() -> Vec<u8> {
+    // lut16 from Lab to CMYK with two grid points per channel, black ink falls with L*
+    let mut lut = b"mft2\0\0\0\0".to_vec();
+    lut.extend([3, 4, 2, 0]);
+    for i in 0..9 {
+        let value: i32 = if i % 4 == 0 { 0x10000 } else { 0 };
+        lut.extend(value.to_be_bytes());
+    }
+    lut.extend(2u16.to_be_bytes());
+    lut.extend(2u16.to_be_bytes());
+    let identity = [0u16, 0xFFFF];
+    for _ in 0..3 {
+        lut.extend(identity.iter().flat_map(|v| v.to_be_bytes()));
+    }
+    for l in 0..2 {
+        for _ in 0..4 {
+            let black: u16 = if l == 0 { 0xFFFF } else { 0 };
+            lut.extend([0u16, 0, 0, black].iter().flat_map(|v| v.to_be_bytes()));
+        }
+    }
+    for _ in 0..4 {
+        lut.extend(identity.iter().flat_map(|v| v.to_be_bytes()));
+    }
+
+    let mut icc = vec![0; ICC_HEADER_SIZE];
+    icc[16..20].copy_from_slice(b"CMYK");
+    icc[20..24].copy_from_slice(b"Lab ");
+    icc[36..40].copy_from_slice(b"acsp");
+    icc.extend(1u32.to_be_bytes());
+    icc.extend(b"B2A0");
+    icc.extend((ICC_HEADER_SIZE as u32 + 16).to_be_bytes());
+    icc.extend((lut.len() as u32).to_be_bytes());
+    icc.extend(lut);
+    let size = icc.len() as u32;
+    icc[0..4].copy_from_slice(&size.to_be_bytes());
+    icc
+}
+
+#[test]
+fn srgb_profile_has_the_srgb_primaries() {
+    let icc = srgb_profile();
+
+    assert_eq!(u32_at(&icc, 0) as usize, icc.len());
+    assert_eq!(&icc[36..40], b"acsp");
+    assert_eq!(u32_at(&icc, ICC_HEADER_SIZE), 9);
+    assert_eq!(
+        ascii_description(tag(&icc, b"desc").unwrap()).unwrap(),
+        SRGB_DESCRIPTION
+    );
+    let red = tag(&icc, b"rXYZ").unwrap();
+    // 0.4361 as s15Fixed16 number
+    assert_eq!(u32_at(red, 8), 0x6FA3);
+    assert_eq!(
+        tag(&icc, b"gTRC").unwrap().len(),
+        12 + 2 * SRGB_CURVE_POINTS
+    );
+}
+
+#[test]
+fn rgb_is_converted_with_the_lookup_table_of_the_profile() {
+    let profile = CmykProfile::parse(grey_ramp_cmyk_profile(), "Grey ramp".to_string()).unwrap();
+    assert_eq!(profile.description, "Grey ramp");
+
+    assert_eq!(profile.rgb_to_cmyk([0, 0, 0]), [0, 0, 0, 255]);
+    assert_eq!(profile.rgb_to_cmyk([255, 255, 255]), [0, 0, 0, 1]);
+    // sRGB grey 128 has an L* of 53.6
+    assert_eq!(profile.rgb_to_cmyk([128, 128, 128]), [0, 0, 0, 119]);
+
+    let image = ImageXObject::new(
+        printpdf::Px(2),
+        printpdf::Px(1),
+        ColorSpace::Rgb,
+        printpdf::ColorBits::Bit8,
+        true,
+        None,
+        None,
+        vec![0, 0, 0, 255, 255, 255],
+    );
+    let cmyk = OutputColor::Cmyk(profile).convert(image);
+    assert!(matches!(cmyk.color_space, ColorSpace::Cmyk));
+    assert_eq!(cmyk.image_data, vec![0, 0, 0, 255, 0, 0, 0, 1]);
+}
+
+#[test]
+fn rgb_profiles_are_rejected_for_cmyk() {
+    let mut icc = srgb_profile();
+    assert!(CmykProfile::parse(icc.clone(), String::new()).is_err());
+
+    icc[16..20].copy_from_slice(b"CMYK");
+    icc[20..24].copy_from_slice(b"Lab ");
+    // a CMYK header without a table to convert to CMYK
+    assert!(CmykProfile::parse(icc, String::new()).is_err());
+}
+
+#[test]
+fn truncated_profiles_are_rejected() {
+    let icc = grey_ramp_cmyk_profile();
+    assert!(CmykProfile::parse(icc.clone(), String::new()).is_ok());
+    for length in 0..icc.len() {
+        assert!(
+            CmykProfile::parse(icc[..length].to_vec(), String::new()).is_err(),
+            "{} bytes",
+            length
+        );
+    }
+
+    let description = b"desc\0\0\0\0\0\0\0\x05Grey\0";
+    assert_eq!(ascii_description(description).as_deref(), Some("Grey"));
+    for length in 0..description.len() {
+        assert_eq!(ascii_description(&description[..length]), None);
+    }
+    // a length past the end of the tag
+    assert_eq!(ascii_description(b"desc\0\0\0\0\xFF\xFF\xFF\xFFGrey"), None);
+}
+
+#[test]
+fn xyz_input_goes_through_the_matrix() {
+    let mut icc = grey_ramp_cmyk_profile();
+    icc[20..24].copy_from_slice(b"XYZ ");
+    let identity = CmykProfile::parse(icc.clone(), String::new()).unwrap();
+
+    // the first table input is Y instead of X
+    let matrix = ICC_HEADER_SIZE + 16 + 12;
+    icc[matrix..matrix + 4].copy_from_slice(&0i32.to_be_bytes());
+    icc[matrix + 4..matrix + 8].copy_from_slice(&0x10000i32.to_be_bytes());
+    let y_first = CmykProfile::parse(icc, String::new()).unwrap();
+
+    // X of sRGB red is 0.4361, Y is 0.2225, the black ink falls with the first input
+    assert_eq!(identity.rgb_to_cmyk([255, 0, 0]), [0, 0, 0, 199]);
+    assert_eq!(y_first.rgb_to_cmyk([255, 0, 0]), [0, 0, 0, 227]);
+}
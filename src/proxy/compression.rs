@@ -0,0 +1,178 @@
+//! Size of the saved PDF. printpdf writes the card images as raw pixels and only compresses
+//! them in release builds, so the images are compressed here, losslessly or as JPEG.
+
+use anyhow::{Context, Result};
+use image::codecs::jpeg::JpegEncoder;
+use image::{imageops, ColorType, DynamicImage};
+use printpdf::lopdf;
+use printpdf::{ColorSpace, ImageFilter, ImageXObject};
+
+use super::MM_PER_INCH;
+
+/// How the card images are stored in the PDF.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CompressionFormat {
+    /// Flate compressed pixels, the images keep every detail
+    #[default]
+    Lossless,
+    Jpeg,
+}
+
+impl CompressionFormat {
+    pub const ALL: [CompressionFormat; 2] = [CompressionFormat::Lossless, CompressionFormat::Jpeg];
+}
+
+impl std::fmt::Display for CompressionFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let label = match self {
+            CompressionFormat::Lossless => "Lossless (Flate)",
+            CompressionFormat::Jpeg => "JPEG (smaller file)",
+        };
+        f.write_str(label)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Compression {
+    pub format: CompressionFormat,
+    /// JPEG quality from 1 to 100
+    pub jpeg_quality: u8,
+    /// Downsamples images with a higher resolution to `target_dpi`
+    pub downsample: bool,
+    pub target_dpi: f64,
+}
+
+impl Default for Compression {
+    fn default() -> Self {
+        Compression {
+            format: CompressionFormat::default(),
+            jpeg_quality: 85,
+            downsample: false,
+            target_dpi: 300.0,
+        }
+    }
+}
+
+impl Compression {
+    /// Highest resolution of the printed images, if they are downsampled
+    pub fn max_dpi(&self) -> Option<f64> {
+        self.downsample.then_some(self.target_dpi)
+    }
+}
+
+/// Shrinks an image printed `printed_width_mm` wide to the DPI, smaller images are kept.
+pub fn downsample(
+    image: DynamicImage,
+    printed_width_mm: f64,
+    max_dpi: Option<f64>,
+) -> DynamicImage {
+    let Some(max_dpi) = max_dpi else {
+        return image;
+    };
+    let max_width = (printed_width_mm / MM_PER_INCH * max_dpi).round() as u32;
+    if image.width() <= max_width || max_width == 0 {
+        return image;
+    }
+
+    let height = (image.height() as f64 * max_width as f64 / image.width() as f64).round() as u32;
+    image.resize_exact(max_width, height.max(1), imageops::FilterType::Lanczos3)
+}
+
+/// Encodes the pixels as JPEG for the JPEG format, lossless images are compressed when the
+/// PDF is saved.
+pub fn compress_image(
+    image_x_object: ImageXObject,
+    compression: Compression,
+) -> Result<ImageXObject> {
+    let color_type = match image_x_object.color_space {
+        ColorSpace::Rgb => ColorType::Rgb8,
+        ColorSpace::Greyscale => ColorType::L8,
+        _ => return Ok(image_x_object),
+    };
+    if compression.format == CompressionFormat::Lossless || image_x_object.image_filter.is_some() {
+        return Ok(image_x_object);
+    }
+
+    let mut jpeg = Vec::new();
+    JpegEncoder::new_with_quality(&mut jpeg, compression.jpeg_quality.clamp(1, 100))
+        .encode(
+            &image_x_object.image_data,
+            image_x_object.width.0 as u32,
+            image_x_object.height.0 as u32,
+            color_type,
+        )
+        .context("Failed to encode card image as JPEG")?;
+
+    Ok(ImageXObject {
+        image_data: jpeg,
+        image_filter: Some(ImageFilter::DCT),
+        ..image_x_object
+    })
+}
+
+/// Flate compresses every stream of the PDF that is neither compressed nor a JPEG yet.
+pub fn compress_pdf(pdf_bytes: Vec<u8>) -> Result<Vec<u8>> {
+    let mut doc = lopdf::Document::load_mem(&pdf_bytes).context("Failed to read created PDF")?;
+    doc.compress();
+
+    let mut compressed = Vec::new();
+    doc.save_to(&mut compressed)
+        .context("Failed to write compressed PDF")?;
+    Ok(compressed)
+}
+
+/// File size in KB or MB, as shown after saving
+pub fn format_file_size(bytes: u64) -> String {
+    const KB: f64 = 1024.0;
+    let bytes = bytes as f64;
+    if bytes < KB * KB {
+        format!("{:.0} KB", bytes / KB)
+    } else {
+        format!("{:.1} MB", bytes / (KB * KB))
+    }
+}
+
+#[cfg(test)]
+fn gradient_image(width: u32, height: u32) -> DynamicImage {
+    DynamicImage::ImageRgb8(image::RgbImage::from_fn(width, height, |x, y| {
+        let value = ((x + y) / 2 % 256) as u8;
+        image::Rgb([value, value / 2, 255 - value])
+    }))
+}
+
+#[test]
+fn only_larger_images_are_downsampled() {
+    // 63 mm at 300 DPI are 744 pixels
+    let image = downsample(gradient_image(2976, 4156), 63.0, Some(300.0));
+    assert_eq!((image.width(), image.height()), (744, 1039));
+
+    let image = downsample(gradient_image(672, 936), 63.0, Some(300.0));
+    assert_eq!((image.width(), image.height()), (672, 936));
+
+    let image = downsample(gradient_image(2976, 4156), 63.0, None);
+    assert_eq!(image.width(), 2976);
+}
+
+#[test]
+fn jpeg_images_are_smaller_than_their_pixels() {
+    let image = printpdf::Image::from_dynamic_image(&gradient_image(200, 280)).image;
+    let pixels = image.image_data.len();
+    let jpeg = Compression {
+        format: CompressionFormat::Jpeg,
+        ..Compression::default()
+    };
+
+    let lossless = compress_image(image.clone(), Compression::default()).unwrap();
+    let compressed = compress_image(image, jpeg).unwrap();
+
+    assert_eq!(lossless.image_data.len(), pixels);
+    assert!(matches!(compressed.image_filter, Some(ImageFilter::DCT)));
+    assert!(compressed.image_data.starts_with(&[0xFF, 0xD8]));
+    assert!(compressed.image_data.len() < pixels / 4);
+}
+
+#[test]
+fn file_sizes_are_shown_in_kb_or_mb() {
+    assert_eq!(format_file_size(300 * 1024), "300 KB");
+    assert_eq!(format_file_size(5 * 1024 * 1024 + 300 * 1024), "5.3 MB");
+}
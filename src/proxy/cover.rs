@@ -0,0 +1,387 @@
+//! Optional first page with the deck name and a checklist of the printed cards.
+
+use std::path::Path;
+
+use anyhow::Result;
+use printpdf::{
+    BuiltinFont, IndirectFontRef, Line, Mm, PdfDocumentReference, PdfLayerIndex, PdfLayerReference,
+    PdfPageIndex, Point,
+};
+
+use super::{output, CardImage, CardLabel, PAGE_X, PAGE_Y};
+
+const MARGIN_MM: f64 = 15.0;
+const TITLE_FONT_SIZE: f64 = 20.0;
+const HEADING_FONT_SIZE: f64 = 11.0;
+const TEXT_FONT_SIZE: f64 = 9.0;
+const LINE_HEIGHT_MM: f64 = 4.5;
+const CHECKLIST_COLUMNS: usize = 2;
+const COLUMN_GAP_MM: f64 = 10.0;
+const CHECKBOX_MM: f64 = 2.5;
+// Longer captions are cut off so they stay inside their column
+const MAX_CAPTION_CHARS: usize = 44;
+// Section of the cards listed before the first section header
+const DEFAULT_SECTION: &str = "Cards";
+
+#[derive(Debug, PartialEq)]
+struct ChecklistLine {
+    quantity: usize,
+    caption: String,
+    /// The card is not on the sheets because its image could not be loaded
+    missing: bool,
+}
+
+#[derive(Debug, PartialEq)]
+struct ChecklistSection {
+    title: String,
+    lines: Vec<ChecklistLine>,
+}
+
+impl ChecklistSection {
+    fn is_tokens(&self) -> bool {
+        self.title.to_lowercase().contains("token")
+    }
+
+    fn quantity(&self) -> usize {
+        self.lines.iter().map(|line| line.quantity).sum()
+    }
+}
+
+/// Printed and missing cards, grouped by decklist section.
+#[derive(Debug, Default, PartialEq)]
+pub(super) struct Checklist {
+    sections: Vec<ChecklistSection>,
+}
+
+impl Checklist {
+    /// `cards` are the card images going into the PDF, `missing` the cards that were not found
+    /// on Scryfall, with their quantity.
+    pub(super) fn new(cards: &[CardImage], missing: &[(CardLabel, usize)]) -> Self {
+        let mut checklist = Checklist::default();
+        // Back faces are part of the card of their front face
+        for card in cards.iter().filter(|card| !card.back_face) {
+            checklist.add(&card.label, card.image.is_err(), 1);
+        }
+        for (label, quantity) in missing {
+            checklist.add(label, true, *quantity);
+        }
+        checklist
+    }
+
+    fn add(&mut self, label: &CardLabel, missing: bool, quantity: usize) {
+        let title = label.section.as_deref().unwrap_or(DEFAULT_SECTION);
+        let index = match self.sections.iter().position(|s| s.title == title) {
+            Some(index) => index,
+            None => {
+                self.sections.push(ChecklistSection {
+                    title: title.to_string(),
+                    lines: Vec::new(),
+                });
+                self.sections.len() - 1
+            }
+        };
+
+        let caption = label.caption();
+        let lines = &mut self.sections[index].lines;
+        match lines
+            .iter_mut()
+            .find(|line| line.caption == caption && line.missing == missing)
+        {
+            Some(line) => line.quantity += quantity,
+            None => lines.push(ChecklistLine {
+                quantity,
+                caption,
+                missing,
+            }),
+        }
+    }
+
+    /// Printed cards outside of token sections
+    fn card_count(&self) -> usize {
+        self.count(|section, line| !section.is_tokens() && !line.missing)
+    }
+
+    fn token_count(&self) -> usize {
+        self.count(|section, line| section.is_tokens() && !line.missing)
+    }
+
+    fn missing_count(&self) -> usize {
+        self.count(|_, line| line.missing)
+    }
+
+    fn count(&self, filter: impl Fn(&ChecklistSection, &ChecklistLine) -> bool) -> usize {
+        self.sections
+            .iter()
+            .flat_map(|section| section.lines.iter().map(move |line| (section, line)))
+            .filter(|(section, line)| filter(section, line))
+            .map(|(_, line)| line.quantity)
+            .sum()
+    }
+}
+
+/// Draws the cover onto the given page, adding pages if the checklist doesn't fit.
+/// Returns the number of pages used.
+pub(super) fn draw_cover(
+    doc: &PdfDocumentReference,
+    page: PdfPageIndex,
+    layer: PdfLayerIndex,
+    deck_name: &str,
+    source_file: &Path,
+    checklist: &Checklist,
+) -> Result<usize> {
+    let mut writer = CoverWriter {
+        doc,
+        layer: doc.get_page(page).get_layer(layer),
+        regular: doc.add_builtin_font(BuiltinFont::Helvetica)?,
+        bold: doc.add_builtin_font(BuiltinFont::HelveticaBold)?,
+        pages: 1,
+        column: 0,
+        top: PAGE_Y - MARGIN_MM,
+        y: PAGE_Y - MARGIN_MM,
+    };
+
+    writer.y -= TITLE_FONT_SIZE * 0.35;
+    writer.text(deck_name, TITLE_FONT_SIZE, true, MARGIN_MM, writer.y);
+    writer.y -= LINE_HEIGHT_MM * 2.0;
+
+    let source = source_file
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let mut summary = vec![
+        format!("Decklist: {}", source),
+        format!("Generated: {}", output::today()),
+        format!("Cards: {}", checklist.card_count()),
+    ];
+    if checklist.token_count() > 0 {
+        summary.push(format!("Tokens: {}", checklist.token_count()));
+    }
+    if checklist.missing_count() > 0 {
+        summary.push(format!(
+            "Missing: {} (listed below, not on the sheets)",
+            checklist.missing_count()
+        ));
+    }
+    for line in summary {
+        writer.text(&line, TEXT_FONT_SIZE, false, MARGIN_MM, writer.y);
+        writer.y -= LINE_HEIGHT_MM;
+    }
+
+    writer.y -= LINE_HEIGHT_MM;
+    writer.top = writer.y;
+
+    for section in &checklist.sections {
+        let (x, y) = writer.next_line();
+        let heading = format!("{} ({})", section.title, section.quantity());
+        writer.text(&heading, HEADING_FONT_SIZE, true, x, y);
+
+        for line in &section.lines {
+            let (x, y) = writer.next_line();
+            writer.checkbox(x, y);
+            let mut caption = line.caption.clone();
+            if caption.chars().count() > MAX_CAPTION_CHARS {
+                caption = caption.chars().take(MAX_CAPTION_CHARS - 3).collect();
+                caption.push_str("...");
+            }
+            if line.missing {
+                caption.push_str(" - missing");
+            }
+            let row = format!("{}  {}", line.quantity, caption);
+            writer.text(&row, TEXT_FONT_SIZE, line.missing, x + CHECKBOX_MM + 1.5, y);
+        }
+        writer.y -= LINE_HEIGHT_MM / 2.0;
+    }
+
+    Ok(writer.pages)
+}
+
+/// Writes lines top to bottom, continuing in the next column or on a new page.
+struct CoverWriter<'a> {
+    doc: &'a PdfDocumentReference,
+    layer: PdfLayerReference,
+    regular: IndirectFontRef,
+    bold: IndirectFontRef,
+    pages: usize,
+    column: usize,
+    /// Baseline of the first checklist line on the current page
+    top: f64,
+    /// Baseline of the next line
+    y: f64,
+}
+
+impl CoverWriter<'_> {
+    fn next_line(&mut self) -> (f64, f64) {
+        if self.y < MARGIN_MM {
+            self.column += 1;
+            self.y = self.top;
+        }
+        if self.column == CHECKLIST_COLUMNS {
+            let (page, layer) = self.doc.add_page(Mm(PAGE_X), Mm(PAGE_Y), "Checklist");
+            self.layer = self.doc.get_page(page).get_layer(layer);
+            self.pages += 1;
+            self.column = 0;
+            self.top = PAGE_Y - MARGIN_MM - HEADING_FONT_SIZE * 0.35;
+            self.y = self.top;
+        }
+
+        let column_width = (PAGE_X - 2.0 * MARGIN_MM - COLUMN_GAP_MM) / CHECKLIST_COLUMNS as f64;
+        let x = MARGIN_MM + self.column as f64 * (column_width + COLUMN_GAP_MM);
+        let y = self.y;
+        self.y -= LINE_HEIGHT_MM;
+        (x, y)
+    }
+
+    fn text(&self, text: &str, font_size: f64, bold: bool, x: f64, y: f64) {
+        let font = if bold { &self.bold } else { &self.regular };
+        self.layer.use_text(text, font_size, Mm(x), Mm(y), font);
+    }
+
+    fn checkbox(&self, x: f64, y: f64) {
+        let corners = [
+            (x, y),
+            (x + CHECKBOX_MM, y),
+            (x + CHECKBOX_MM, y + CHECKBOX_MM),
+            (x, y + CHECKBOX_MM),
+        ];
+        self.layer.set_outline_thickness(0.5);
+        self.layer.add_shape(Line {
+            points: corners
+                .iter()
+                .map(|&(x, y)| (Point::new(Mm(x), Mm(y)), false))
+                .collect(),
+            is_closed: true,
+            has_fill: false,
+            has_stroke: true,
+            is_clipping_path: false,
+        });
+    }
+}
+
+#[cfg(test)]
+fn test_card(name: &str, section: Option<&str>, back_face: bool, found: bool) -> CardImage {
+    let pixels = image::DynamicImage::ImageRgb8(image::RgbImage::new(63, 88));
+    CardImage {
+        label: CardLabel {
+            name: name.to_string(),
+            set: None,
+            collector_number: None,
+            section: section.map(String::from),
+            card: None,
+        },
+        back_face,
+        image: if found {
+            Ok(std::sync::Arc::new(printpdf::Image::from_dynamic_image(
+                &pixels,
+            )))
+        } else {
+            Err(anyhow::anyhow!("Failed to download '{}'", name))
+        },
+    }
+}
+
+#[cfg(test)]
+fn line(quantity: usize, caption: &str, missing: bool) -> ChecklistLine {
+    ChecklistLine {
+        quantity,
+        caption: caption.to_string(),
+        missing,
+    }
+}
+
+#[test]
+fn copies_are_one_line_and_back_faces_are_not_counted() {
+    let cards = super::repeat_copies(
+        vec![
+            test_card("Lightning Bolt", None, false, true),
+            test_card("Delver of Secrets", None, false, true),
+            test_card("Insectile Aberration", None, true, true),
+        ],
+        &[(1, 3), (2, 2)],
+    );
+    assert_eq!(cards.len(), 7);
+
+    let checklist = Checklist::new(&cards, &[]);
+    assert_eq!(
+        checklist.sections,
+        [ChecklistSection {
+            title: DEFAULT_SECTION.to_string(),
+            lines: vec![
+                line(3, "Lightning Bolt", false),
+                line(2, "Delver of Secrets", false),
+            ],
+        }]
+    );
+    assert_eq!(checklist.card_count(), 5);
+    assert_eq!(checklist.missing_count(), 0);
+}
+
+#[test]
+fn counts_match_the_cards_on_the_sheets() {
+    let cards = || {
+        vec![
+            test_card("Island", Some("Main"), false, true),
+            test_card("Island", Some("Main"), false, true),
+            // the download failed, one copy of the card is missing on the sheets
+            test_card("Island", Some("Main"), false, false),
+            test_card("Brazen Borrower", Some("Main"), false, false),
+            test_card("Petty Theft", Some("Main"), true, false),
+            test_card("Goblin", Some("Tokens"), false, true),
+            test_card("Goblin", Some("Tokens"), false, true),
+        ]
+    };
+    // not found on Scryfall, all four copies are missing
+    let not_found = [(
+        CardLabel {
+            name: "Mistyped Card".to_string(),
+            set: None,
+            collector_number: None,
+            section: Some("Sideboard".to_string()),
+            card: None,
+        },
+        4,
+    )];
+
+    let checklist = Checklist::new(&cards(), &not_found);
+    assert_eq!(
+        checklist.sections,
+        [
+            ChecklistSection {
+                title: "Main".to_string(),
+                lines: vec![
+                    line(2, "Island", false),
+                    line(1, "Island", true),
+                    line(1, "Brazen Borrower", true),
+                ],
+            },
+            ChecklistSection {
+                title: "Tokens".to_string(),
+                lines: vec![line(2, "Goblin", false)],
+            },
+            ChecklistSection {
+                title: "Sideboard".to_string(),
+                lines: vec![line(4, "Mistyped Card", true)],
+            },
+        ]
+    );
+    assert!(checklist.sections[1].is_tokens());
+    assert_eq!(checklist.sections[0].quantity(), 4);
+    assert_eq!(
+        (
+            checklist.card_count(),
+            checklist.token_count(),
+            checklist.missing_count()
+        ),
+        (2, 2, 6)
+    );
+
+    let sheets = super::grid_sheets(cards(), &None, &super::ProxyOptions::default()).unwrap();
+    let placed_fronts = sheets
+        .iter()
+        .flat_map(|sheet| &sheet.cards)
+        .filter(|card| card.front)
+        .count();
+    assert_eq!(
+        placed_fronts,
+        checklist.card_count() + checklist.token_count()
+    );
+}
@@ -0,0 +1,136 @@
+//! Back pages for double-sided printing.
+
+use super::layout::Rect;
+use super::{PAGE_X, PAGE_Y};
+
+/// Page edge the printer turns the sheet over for the back side.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DuplexFlip {
+    /// Back pages are mirrored left to right
+    #[default]
+    LongEdge,
+    /// Back pages are mirrored top to bottom
+    ShortEdge,
+}
+
+impl DuplexFlip {
+    pub const ALL: [DuplexFlip; 2] = [DuplexFlip::LongEdge, DuplexFlip::ShortEdge];
+}
+
+impl std::fmt::Display for DuplexFlip {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let label = match self {
+            DuplexFlip::LongEdge => "Flip on long edge",
+            DuplexFlip::ShortEdge => "Flip on short edge",
+        };
+        f.write_str(label)
+    }
+}
+
+/// Adds a back page after every card page, with each back behind its front.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Duplex {
+    pub flip: DuplexFlip,
+    /// Shift of the back pages to the right in mm, to compensate printer misalignment
+    pub offset_x_mm: f64,
+    /// Shift of the back pages upwards in mm
+    pub offset_y_mm: f64,
+}
+
+impl Duplex {
+    /// Slot on the back page behind the given slot of the front page.
+    pub(super) fn back_slot(&self, slot: Rect) -> Rect {
+        let (x, y) = match self.flip {
+            DuplexFlip::LongEdge => (PAGE_X - slot.x - slot.width, slot.y),
+            DuplexFlip::ShortEdge => (slot.x, PAGE_Y - slot.y - slot.height),
+        };
+        Rect {
+            x: x + self.offset_x_mm,
+            y: y + self.offset_y_mm,
+            ..slot
+        }
+    }
+}
+
+#[cfg(test)]
+fn slot(x: f64, y: f64) -> Rect {
+    Rect {
+        x,
+        y,
+        width: 63.0,
+        height: 88.0,
+    }
+}
+
+// Slots of a 3 x 3 grid of 63 x 88 mm cards, centered on the A4 page
+#[cfg(test)]
+const TOP_LEFT: (f64, f64) = (10.5, 192.5);
+#[cfg(test)]
+const MIDDLE: (f64, f64) = (73.5, 104.5);
+
+#[test]
+fn backs_of_corner_cards_are_in_the_mirrored_corner() {
+    let long_edge = Duplex::default();
+    let short_edge = Duplex {
+        flip: DuplexFlip::ShortEdge,
+        ..Duplex::default()
+    };
+    let top_left = slot(TOP_LEFT.0, TOP_LEFT.1);
+
+    // top right and bottom left, seen from the back
+    assert_eq!(long_edge.back_slot(top_left), slot(136.5, 192.5));
+    assert_eq!(short_edge.back_slot(top_left), slot(10.5, 16.5));
+    // flipping twice gets back to the front
+    assert_eq!(long_edge.back_slot(long_edge.back_slot(top_left)), top_left);
+    assert_eq!(
+        short_edge.back_slot(short_edge.back_slot(top_left)),
+        top_left
+    );
+}
+
+#[test]
+fn backs_of_middle_cards_stay_in_the_middle() {
+    let middle = slot(MIDDLE.0, MIDDLE.1);
+    for flip in DuplexFlip::ALL {
+        let duplex = Duplex {
+            flip,
+            ..Duplex::default()
+        };
+        assert_eq!(duplex.back_slot(middle), middle, "{}", flip);
+    }
+}
+
+#[test]
+fn the_offset_moves_every_back() {
+    for flip in DuplexFlip::ALL {
+        let aligned = Duplex {
+            flip,
+            ..Duplex::default()
+        };
+        let shifted = Duplex {
+            offset_x_mm: 1.5,
+            offset_y_mm: -0.75,
+            ..aligned
+        };
+        for front in [slot(TOP_LEFT.0, TOP_LEFT.1), slot(MIDDLE.0, MIDDLE.1)] {
+            let back = aligned.back_slot(front);
+            assert_eq!(
+                shifted.back_slot(front),
+                Rect {
+                    x: back.x + 1.5,
+                    y: back.y - 0.75,
+                    ..back
+                },
+                "{}",
+                flip
+            );
+        }
+    }
+    let shifted_corner = Duplex {
+        offset_x_mm: 1.5,
+        offset_y_mm: -0.75,
+        ..Duplex::default()
+    }
+    .back_slot(slot(TOP_LEFT.0, TOP_LEFT.1));
+    assert_eq!(shifted_corner, slot(138.0, 191.75));
+}
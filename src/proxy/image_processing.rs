@@ -0,0 +1,357 @@
+//! Processing steps applied to the decoded card images before they are laid out.
+
+use image::{imageops, DynamicImage, Rgba, RgbaImage};
+use printpdf::{ColorSpace, ImageXObject};
+
+use super::{AlphaPixels, MM_PER_INCH};
+
+// How much of the original darkness the low ink mode keeps outside of outlines
+const LOW_INK_DARKNESS: f64 = 0.2;
+// Amplification of the edge strength into outline darkness
+const LOW_INK_OUTLINE_GAIN: f64 = 1.5;
+// Blur radius of the sharpening after upscaling, in pixels of the source image
+const UPSCALE_SHARPEN_SIGMA: f32 = 0.6;
+// Brightness difference below which the sharpening leaves pixels alone
+const UPSCALE_SHARPEN_THRESHOLD: i32 = 2;
+
+/// How the extended border fits into the card slot on the page.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BorderFit {
+    /// The card including the extension keeps the card size, the card itself gets smaller
+    #[default]
+    ShrinkCard,
+    /// The card keeps its size and the extension reaches past the slot
+    Overflow,
+}
+
+/// Solid border around each card, filled with the color of the card frame,
+/// so slightly off cuts don't show the white paper.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct BorderExtension {
+    pub width_mm: f64,
+    pub fit: BorderFit,
+}
+
+impl BorderExtension {
+    /// Distance the image reaches past the card slot on each side
+    pub fn overflow_mm(&self) -> f64 {
+        match self.fit {
+            BorderFit::Overflow => self.width_mm,
+            BorderFit::ShrinkCard => 0.0,
+        }
+    }
+}
+
+/// Adds the border around an image of a card that is printed `card_width_mm` wide.
+pub fn extend_border(
+    image: DynamicImage,
+    border: BorderExtension,
+    card_width_mm: f64,
+) -> DynamicImage {
+    if border.width_mm <= 0.0 {
+        return image;
+    }
+
+    let card = image.to_rgba8();
+    let (width, height) = card.dimensions();
+    let px_per_mm = width as f64 / card_width_mm;
+    let border_px = (border.width_mm * px_per_mm).round() as u32;
+
+    let pixels = AlphaPixels {
+        data: card.as_raw(),
+        width: width as usize,
+        height: height as usize,
+        channels: 4,
+    };
+    let [red, green, blue]: [u8; 3] = pixels.sample_border_color().try_into().unwrap();
+
+    let (extended_width, extended_height, card) = match border.fit {
+        BorderFit::ShrinkCard => {
+            // the same factor for both sides keeps the proportions of the art, the border is
+            // a bit wider at the top and bottom
+            let scale = ((width as f64 - 2.0 * border_px as f64) / width as f64)
+                .min((height as f64 - 2.0 * border_px as f64) / height as f64)
+                .max(0.0);
+            let card_width = ((width as f64 * scale).round() as u32).max(1);
+            let card_height = ((height as f64 * scale).round() as u32).max(1);
+            let card = imageops::resize(
+                &card,
+                card_width,
+                card_height,
+                imageops::FilterType::CatmullRom,
+            );
+            (width, height, card)
+        }
+        BorderFit::Overflow => (width + 2 * border_px, height + 2 * border_px, card),
+    };
+
+    let mut extended = RgbaImage::from_pixel(
+        extended_width,
+        extended_height,
+        Rgba([red, green, blue, u8::MAX]),
+    );
+    // blending over the border color also fills the transparent rounded corners
+    imageops::overlay(
+        &mut extended,
+        &card,
+        (extended_width - card.width()) as i64 / 2,
+        (extended_height - card.height()) as i64 / 2,
+    );
+
+    DynamicImage::ImageRgba8(extended)
+}
+
+/// Upscaling of low resolution images like custom images and older scans, which print
+/// blurry at card size.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Upscaling {
+    pub enabled: bool,
+    /// Resolution the images are brought up to
+    pub target_dpi: f64,
+    /// Images below this resolution get a warning, upscaled or not
+    pub min_dpi: f64,
+}
+
+impl Default for Upscaling {
+    fn default() -> Self {
+        Upscaling {
+            enabled: false,
+            target_dpi: 300.0,
+            min_dpi: 200.0,
+        }
+    }
+}
+
+/// Resolution of the image when it is printed `printed_width_mm` wide
+pub fn effective_dpi(image: &DynamicImage, printed_width_mm: f64) -> f64 {
+    image.width() as f64 / (printed_width_mm / MM_PER_INCH)
+}
+
+/// Resizes images below the target DPI with Lanczos3 and sharpens the edges the
+/// interpolation softened. Images with enough resolution are kept.
+pub fn upscale(image: DynamicImage, printed_width_mm: f64, upscaling: Upscaling) -> DynamicImage {
+    let factor = upscaling.target_dpi / effective_dpi(&image, printed_width_mm);
+    if !upscaling.enabled || factor <= 1.0 {
+        return image;
+    }
+
+    let width = (image.width() as f64 * factor).round() as u32;
+    let height = (image.height() as f64 * factor).round() as u32;
+    image
+        .resize_exact(width, height, imageops::FilterType::Lanczos3)
+        .unsharpen(
+            UPSCALE_SHARPEN_SIGMA * factor as f32,
+            UPSCALE_SHARPEN_THRESHOLD,
+        )
+}
+
+/// Ink usage of the printed cards.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PrintMode {
+    #[default]
+    Color,
+    Greyscale,
+    /// Greyscale outlines of the text and frame, with the art and filled areas lightened
+    LowInk,
+}
+
+impl PrintMode {
+    pub const ALL: [PrintMode; 3] = [PrintMode::Color, PrintMode::Greyscale, PrintMode::LowInk];
+}
+
+impl std::fmt::Display for PrintMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let label = match self {
+            PrintMode::Color => "Color",
+            PrintMode::Greyscale => "Greyscale",
+            PrintMode::LowInk => "Low ink (text and outlines)",
+        };
+        f.write_str(label)
+    }
+}
+
+/// Converts RGB images to a single greyscale channel, which also makes the PDF smaller.
+pub fn apply_print_mode(image_x_object: ImageXObject, print_mode: PrintMode) -> ImageXObject {
+    let channels = match image_x_object.color_space {
+        ColorSpace::Rgb => 3,
+        ColorSpace::Greyscale => 1,
+        _ => return image_x_object,
+    };
+    if print_mode == PrintMode::Color {
+        return image_x_object;
+    }
+
+    let luma: Vec<u8> = image_x_object
+        .image_data
+        .chunks_exact(channels)
+        .map(|pixel| match pixel {
+            [red, green, blue] => {
+                (0.299 * *red as f64 + 0.587 * *green as f64 + 0.114 * *blue as f64).round() as u8
+            }
+            [grey] => *grey,
+            _ => unreachable!(),
+        })
+        .collect();
+
+    let image_data = match print_mode {
+        PrintMode::LowInk => outline(&luma, image_x_object.width.0, image_x_object.height.0),
+        _ => luma,
+    };
+
+    ImageXObject {
+        color_space: ColorSpace::Greyscale,
+        image_data,
+        ..image_x_object
+    }
+}
+
+/// Keeps the edges of the image (text strokes, frame lines) dark and lightens everything else.
+fn outline(luma: &[u8], width: usize, height: usize) -> Vec<u8> {
+    let at = |x: usize, y: usize| luma[y * width + x] as f64;
+    let mut outlined = Vec::with_capacity(luma.len());
+
+    for y in 0..height {
+        for x in 0..width {
+            let value = at(x, y);
+            let lightened = 255.0 - (255.0 - value) * LOW_INK_DARKNESS;
+
+            // Sobel operator, clamped at the image edges
+            let (left, right) = (x.saturating_sub(1), (x + 1).min(width - 1));
+            let (up, down) = (y.saturating_sub(1), (y + 1).min(height - 1));
+            let gx = at(right, up) + 2.0 * at(right, y) + at(right, down)
+                - at(left, up)
+                - 2.0 * at(left, y)
+                - at(left, down);
+            let gy = at(left, down) + 2.0 * at(x, down) + at(right, down)
+                - at(left, up)
+                - 2.0 * at(x, up)
+                - at(right, up);
+            let edge = (gx.hypot(gy) / 4.0 * LOW_INK_OUTLINE_GAIN).min(255.0);
+
+            outlined.push(lightened.min(255.0 - edge) as u8);
+        }
+    }
+
+    outlined
+}
+
+#[test]
+fn only_low_resolution_images_are_upscaled() {
+    let upscaling = Upscaling {
+        enabled: true,
+        ..Upscaling::default()
+    };
+    let scan = DynamicImage::ImageRgb8(image::RgbImage::new(300, 420));
+    assert!((effective_dpi(&scan, 63.0) - 120.95).abs() < 0.01);
+
+    // 63 mm at 300 DPI are 744 pixels
+    let upscaled = upscale(scan.clone(), 63.0, upscaling);
+    assert_eq!((upscaled.width(), upscaled.height()), (744, 1042));
+
+    let png = DynamicImage::ImageRgb8(image::RgbImage::new(745, 1040));
+    assert_eq!(upscale(png, 63.0, upscaling).width(), 745);
+    assert_eq!(upscale(scan, 63.0, Upscaling::default()).width(), 300);
+}
+
+#[test]
+fn shrinking_the_card_for_the_border_keeps_its_proportions() {
+    // 63 x 88 mm at 10 pixels per mm, dark art inside a light frame with a grey outer edge
+    let mut card = RgbaImage::from_pixel(630, 880, Rgba([230, 230, 230, u8::MAX]));
+    for (x, y, pixel) in card.enumerate_pixels_mut() {
+        if (115..515).contains(&x) && (140..740).contains(&y) {
+            *pixel = Rgba([20, 20, 20, u8::MAX]);
+        } else if x.min(629 - x) < 5 || y.min(879 - y) < 5 {
+            *pixel = Rgba([150, 150, 150, u8::MAX]);
+        }
+    }
+    let border = BorderExtension {
+        width_mm: 3.0,
+        fit: BorderFit::ShrinkCard,
+    };
+
+    let extended = extend_border(DynamicImage::ImageRgba8(card), border, 63.0).to_rgba8();
+    assert_eq!(extended.dimensions(), (630, 880));
+
+    // bounding box of the dark art in the result
+    let dark: Vec<_> = extended
+        .enumerate_pixels()
+        .filter(|(_, _, pixel)| pixel[0] < 128)
+        .map(|(x, y, _)| (x, y))
+        .collect();
+    let left = dark.iter().map(|&(x, _)| x).min().unwrap();
+    let right = dark.iter().map(|&(x, _)| x).max().unwrap() + 1;
+    let top = dark.iter().map(|&(_, y)| y).min().unwrap();
+    let bottom = dark.iter().map(|&(_, y)| y).max().unwrap() + 1;
+    let (art_width, art_height) = ((right - left) as f64, (bottom - top) as f64);
+
+    assert!((art_height / art_width - 1.5).abs() < 0.01);
+    assert!(art_width < 400.0);
+    // centered on the page
+    assert!(left.abs_diff(630 - right) <= 1 && top.abs_diff(880 - bottom) <= 1);
+    // a full 3 mm border on the narrow side, with the edge of the card right inside it
+    assert_eq!(extended.get_pixel(29, 440).0, [230, 230, 230, u8::MAX]);
+    assert!((140..=160).contains(&extended.get_pixel(31, 440)[0]));
+    assert!((140..=160).contains(&extended.get_pixel(598, 440)[0]));
+
+    let overflow = BorderExtension {
+        fit: BorderFit::Overflow,
+        ..border
+    };
+    let card = DynamicImage::ImageRgba8(RgbaImage::new(630, 880));
+    let extended = extend_border(card, overflow, 63.0);
+    assert_eq!((extended.width(), extended.height()), (690, 940));
+}
+
+#[cfg(test)]
+fn rgb_image_x_object(width: usize, height: usize, image_data: Vec<u8>) -> ImageXObject {
+    ImageXObject::new(
+        printpdf::Px(width),
+        printpdf::Px(height),
+        ColorSpace::Rgb,
+        printpdf::ColorBits::Bit8,
+        false,
+        None,
+        None,
+        image_data,
+    )
+}
+
+#[test]
+fn greyscale_keeps_one_channel_and_color_leaves_the_image_alone() {
+    // red, green, blue, white, black, grey
+    let data = vec![
+        255, 0, 0, 0, 255, 0, 0, 0, 255, 255, 255, 255, 0, 0, 0, 128, 128, 128,
+    ];
+    let image = rgb_image_x_object(3, 2, data.clone());
+
+    let grey = apply_print_mode(image.clone(), PrintMode::Greyscale);
+    assert!(matches!(grey.color_space, ColorSpace::Greyscale));
+    assert_eq!(grey.image_data.len(), 3 * 2);
+    assert_eq!(grey.image_data, vec![76, 150, 29, 255, 0, 128]);
+
+    let color = apply_print_mode(image, PrintMode::Color);
+    assert!(matches!(color.color_space, ColorSpace::Rgb));
+    assert_eq!(color.image_data, data);
+}
+
+#[test]
+fn low_ink_lightens_flat_areas_and_keeps_edges_dark() {
+    // black left half, white right half
+    let (width, height) = (20, 10);
+    let luma: Vec<u8> = (0..width * height)
+        .map(|i| if i % width < width / 2 { 0 } else { 255 })
+        .collect();
+    let at = |image: &[u8], x: usize, y: usize| image[y * width + x];
+
+    let outlined = outline(&luma, width, height);
+    // the flat black keeps a fifth of its darkness, the white stays paper
+    assert_eq!(at(&outlined, 2, 5), 204);
+    assert_eq!(at(&outlined, 16, 5), 255);
+    // the black pixels along the edge stay black
+    assert_eq!(at(&outlined, width / 2 - 1, 5), 0);
+
+    let data = luma.iter().flat_map(|&value| [value; 3]).collect();
+    let low_ink = apply_print_mode(rgb_image_x_object(width, height, data), PrintMode::LowInk);
+    assert!(matches!(low_ink.color_space, ColorSpace::Greyscale));
+    assert_eq!(low_ink.image_data, outlined);
+}
@@ -0,0 +1,467 @@
+//! Placement of the cards on the pages, independent of the PDF.
+
+use super::{CARD_HEIGHT_MM, CARD_WIDTH_MM, PAGE_X, PAGE_Y};
+
+/// Smallest distance of the cards from the page edge, most printers can't print closer
+pub const PAGE_MARGIN_MM: f64 = 5.0;
+// Distance of the cut marks from the printed cards and their length
+const CUT_MARK_GAP_MM: f64 = 1.0;
+const CUT_MARK_LENGTH_MM: f64 = 3.0;
+
+/// How the cards are arranged on the pages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PageLayout {
+    /// One card in the center of each page
+    #[default]
+    Single,
+    /// As many cards as fit on each page
+    Grid,
+    /// Fronts on the left half and backs on the right half, folded along the middle
+    Gutterfold,
+}
+
+impl PageLayout {
+    pub const ALL: [PageLayout; 3] = [PageLayout::Single, PageLayout::Grid, PageLayout::Gutterfold];
+
+    /// Name of the layout for the `{layout}` placeholder of the output file name
+    pub fn name(self) -> &'static str {
+        match self {
+            PageLayout::Single => "single",
+            PageLayout::Grid => "grid",
+            PageLayout::Gutterfold => "gutterfold",
+        }
+    }
+}
+
+impl std::fmt::Display for PageLayout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let label = match self {
+            PageLayout::Single => "One card per page",
+            PageLayout::Grid => "Card grid",
+            PageLayout::Gutterfold => "Gutterfold (fronts and backs, fold in the middle)",
+        };
+        f.write_str(label)
+    }
+}
+
+/// Printed size of a card.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CardSize {
+    pub width_mm: f64,
+    pub height_mm: f64,
+}
+
+impl CardSize {
+    /// Magic, Pokémon and most other trading card games
+    pub const STANDARD: CardSize = CardSize {
+        width_mm: CARD_WIDTH_MM,
+        height_mm: CARD_HEIGHT_MM,
+    };
+
+    /// Oversized commander, Planechase and Archenemy cards
+    pub const OVERSIZED: CardSize = CardSize {
+        width_mm: 88.0,
+        height_mm: 125.0,
+    };
+
+    /// The size turned a quarter, for the landscape slots of cards that are read sideways
+    pub fn turned(self) -> CardSize {
+        CardSize {
+            width_mm: self.height_mm,
+            height_mm: self.width_mm,
+        }
+    }
+
+    pub fn is_landscape(self) -> bool {
+        self.width_mm > self.height_mm
+    }
+}
+
+impl Default for CardSize {
+    fn default() -> Self {
+        CardSize::STANDARD
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CardSizePreset {
+    #[default]
+    Standard,
+    /// Yu-Gi-Oh! and Japanese sleeves
+    Japanese,
+    /// Mini American, for playmats and sealed pool displays
+    Mini,
+    /// Oversized commander display cards
+    Oversized,
+    Tarot,
+    Custom,
+}
+
+impl CardSizePreset {
+    pub const ALL: [CardSizePreset; 6] = [
+        CardSizePreset::Standard,
+        CardSizePreset::Japanese,
+        CardSizePreset::Mini,
+        CardSizePreset::Oversized,
+        CardSizePreset::Tarot,
+        CardSizePreset::Custom,
+    ];
+
+    /// Size of the preset, `None` for custom sizes
+    pub fn size(self) -> Option<CardSize> {
+        let (width_mm, height_mm) = match self {
+            CardSizePreset::Standard => return Some(CardSize::STANDARD),
+            CardSizePreset::Japanese => (59.0, 86.0),
+            CardSizePreset::Mini => (41.0, 63.0),
+            CardSizePreset::Oversized => return Some(CardSize::OVERSIZED),
+            CardSizePreset::Tarot => (70.0, 120.0),
+            CardSizePreset::Custom => return None,
+        };
+        Some(CardSize {
+            width_mm,
+            height_mm,
+        })
+    }
+}
+
+impl std::fmt::Display for CardSizePreset {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            CardSizePreset::Standard => "Standard",
+            CardSizePreset::Japanese => "Japanese",
+            CardSizePreset::Mini => "Mini",
+            CardSizePreset::Oversized => "Oversized commander",
+            CardSizePreset::Tarot => "Tarot",
+            CardSizePreset::Custom => return f.write_str("Custom size"),
+        };
+        let size = self.size().unwrap();
+        write!(f, "{} ({} x {} mm)", name, size.width_mm, size.height_mm)
+    }
+}
+
+/// Area on the page in mm, measured from the bottom left page corner.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rect {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+/// Cards in a grid centered on the page, `padding` mm apart.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GridLayout {
+    pub page_width: f64,
+    pub page_height: f64,
+    pub card_width: f64,
+    pub card_height: f64,
+    pub cols: usize,
+    pub rows: usize,
+    pub padding: f64,
+}
+
+impl GridLayout {
+    /// As many cards as fit on A4 inside the page margins, e.g. 3x3 standard cards
+    pub fn fit(card_size: CardSize, padding: f64) -> Self {
+        GridLayout::fit_area(PAGE_X, PAGE_Y, card_size, padding)
+    }
+
+    /// Grid of the fronts on the left half of the page for gutterfold sheets. The margins of
+    /// both halves form the gutter around the fold line.
+    pub fn gutterfold(card_size: CardSize, padding: f64) -> Self {
+        GridLayout::fit_area(PAGE_X / 2.0, PAGE_Y, card_size, padding)
+    }
+
+    fn fit_area(page_width: f64, page_height: f64, card_size: CardSize, padding: f64) -> Self {
+        let padding = padding.max(0.0);
+        let count = |page: f64, card: f64| {
+            // the epsilon keeps exactly fitting cards from being rounded away
+            ((page - 2.0 * PAGE_MARGIN_MM + padding) / (card + padding) + 1e-9).floor() as usize
+        };
+        GridLayout {
+            page_width,
+            page_height,
+            card_width: card_size.width_mm,
+            card_height: card_size.height_mm,
+            cols: count(page_width, card_size.width_mm),
+            rows: count(page_height, card_size.height_mm),
+            padding,
+        }
+    }
+
+    /// One card in the center of each page, or none if the card is larger than the page
+    pub fn single(card_size: CardSize) -> Self {
+        let grid = GridLayout::fit(card_size, 0.0);
+        GridLayout {
+            cols: grid.cols.min(1),
+            rows: grid.rows.min(1),
+            ..grid
+        }
+    }
+
+    pub fn cards_per_page(&self) -> usize {
+        self.cols * self.rows
+    }
+
+    /// Largest padding that keeps the grid inside the page margins.
+    pub fn max_padding(&self) -> f64 {
+        let max_gap = |page: f64, card: f64, count: usize| {
+            let free = page - 2.0 * PAGE_MARGIN_MM - card * count as f64;
+            match count {
+                0 | 1 => f64::INFINITY,
+                count => free / (count - 1) as f64,
+            }
+        };
+        max_gap(self.page_width, self.card_width, self.cols)
+            .min(max_gap(self.page_height, self.card_height, self.rows))
+            .max(0.0)
+    }
+
+    /// Card slots of one page, row by row from the top left.
+    /// The padding is limited to `max_padding`.
+    pub fn slots(&self) -> Vec<Rect> {
+        let padding = self.padding.clamp(0.0, self.max_padding());
+        let grid_width = self.card_width * self.cols as f64 + padding * gaps(self.cols);
+        let grid_height = self.card_height * self.rows as f64 + padding * gaps(self.rows);
+        let left = (self.page_width - grid_width) / 2.0;
+        let top = self.page_height - (self.page_height - grid_height) / 2.0;
+
+        (0..self.cards_per_page())
+            .map(|i| {
+                let col = (i % self.cols) as f64;
+                let row = (i / self.cols) as f64;
+                Rect {
+                    x: left + (self.card_width + padding) * col,
+                    y: top - self.card_height - (self.card_height + padding) * row,
+                    width: self.card_width,
+                    height: self.card_height,
+                }
+            })
+            .collect()
+    }
+
+    /// Slot mirrored at the right edge of the grid area, which is the fold line of
+    /// gutterfold sheets.
+    pub fn folded_slot(&self, slot: &Rect) -> Rect {
+        Rect {
+            x: 2.0 * self.page_width - slot.x - slot.width,
+            ..*slot
+        }
+    }
+
+    /// Card slots for `card_count` cards, split into pages.
+    pub fn pages(&self, card_count: usize) -> Vec<Vec<Rect>> {
+        let slots = self.slots();
+        let mut pages = Vec::new();
+        let mut remaining = card_count;
+        while remaining > 0 && !slots.is_empty() {
+            let count = remaining.min(slots.len());
+            pages.push(slots[..count].to_vec());
+            remaining -= count;
+        }
+        pages
+    }
+}
+
+/// Short lines in line with the card edges outside the cards, as start and end point in mm.
+/// They start `bleed` mm further out, past the border extension overflowing the slots.
+pub fn cut_marks(slots: &[Rect], bleed: f64) -> Vec<[(f64, f64); 2]> {
+    let edges = |edges: &mut dyn Iterator<Item = f64>| {
+        let mut edges: Vec<f64> = edges.collect();
+        edges.sort_by(f64::total_cmp);
+        edges.dedup_by(|a, b| (*a - *b).abs() < 1e-6);
+        edges
+    };
+    let xs = edges(&mut slots.iter().flat_map(|slot| [slot.x, slot.x + slot.width]));
+    let ys = edges(&mut slots.iter().flat_map(|slot| [slot.y, slot.y + slot.height]));
+    let (Some(&left), Some(&right), Some(&bottom), Some(&top)) =
+        (xs.first(), xs.last(), ys.first(), ys.last())
+    else {
+        return Vec::new();
+    };
+
+    let start = bleed.max(0.0) + CUT_MARK_GAP_MM;
+    let end = start + CUT_MARK_LENGTH_MM;
+    let mut marks = Vec::new();
+    for x in xs {
+        marks.push([(x, (top + start).min(PAGE_Y)), (x, (top + end).min(PAGE_Y))]);
+        marks.push([(x, (bottom - start).max(0.0)), (x, (bottom - end).max(0.0))]);
+    }
+    for y in ys {
+        marks.push([((left - start).max(0.0), y), ((left - end).max(0.0), y)]);
+        marks.push([
+            ((right + start).min(PAGE_X), y),
+            ((right + end).min(PAGE_X), y),
+        ]);
+    }
+    // marks of cards at the page edge would have no length
+    marks.retain(|[from, to]| from != to);
+    marks
+}
+
+// Number of gaps between `count` cards in a row or column
+fn gaps(count: usize) -> f64 {
+    count.saturating_sub(1) as f64
+}
+
+#[cfg(test)]
+fn assert_inside_margins(layout: &GridLayout, rect: &Rect) {
+    const EPSILON: f64 = 1e-9;
+    assert!(rect.x >= PAGE_MARGIN_MM - EPSILON, "{rect:?} in {layout:?}");
+    assert!(rect.y >= PAGE_MARGIN_MM - EPSILON, "{rect:?} in {layout:?}");
+    assert!(
+        rect.x + rect.width <= layout.page_width - PAGE_MARGIN_MM + EPSILON,
+        "{rect:?} in {layout:?}"
+    );
+    assert!(
+        rect.y + rect.height <= layout.page_height - PAGE_MARGIN_MM + EPSILON,
+        "{rect:?} in {layout:?}"
+    );
+}
+
+#[test]
+fn grid_is_centered_on_the_page() {
+    let layout = GridLayout::fit(CardSize::STANDARD, 4.0);
+    let slots = layout.slots();
+    let first = slots.first().unwrap();
+    let last = slots.last().unwrap();
+
+    let left = first.x;
+    let right = layout.page_width - (last.x + last.width);
+    let top = layout.page_height - (first.y + first.height);
+    let bottom = last.y;
+    assert!((left - right).abs() < 1e-9);
+    assert!((top - bottom).abs() < 1e-9);
+    // 3 cards and 2 paddings: (297 - 3 * 88 - 2 * 4) / 2
+    assert!((top - 12.5).abs() < 1e-9);
+}
+
+#[cfg(test)]
+fn test_card_sizes() -> Vec<CardSize> {
+    let presets = CardSizePreset::ALL
+        .iter()
+        .filter_map(|preset| preset.size());
+    let custom = (1..=40).flat_map(|w| {
+        (1..=40).map(move |h| CardSize {
+            width_mm: w as f64 * 5.0,
+            height_mm: h as f64 * 7.0,
+        })
+    });
+    presets.chain(custom).collect()
+}
+
+#[test]
+fn standard_cards_fit_three_by_three() {
+    let layout = GridLayout::fit(CardSize::STANDARD, 0.0);
+    assert_eq!((layout.cols, layout.rows), (3, 3));
+    let layout = GridLayout::fit(CardSize::STANDARD, layout.max_padding());
+    assert_eq!((layout.cols, layout.rows), (3, 3));
+}
+
+#[test]
+fn fitted_grid_is_as_large_as_possible() {
+    for card_size in test_card_sizes() {
+        let layout = GridLayout::fit(card_size, 2.0);
+        let extent = |count: usize, card: f64| count as f64 * card + gaps(count) * 2.0;
+        let width = extent(layout.cols + 1, card_size.width_mm);
+        let height = extent(layout.rows + 1, card_size.height_mm);
+        assert!(width > PAGE_X - 2.0 * PAGE_MARGIN_MM, "{layout:?}");
+        assert!(height > PAGE_Y - 2.0 * PAGE_MARGIN_MM, "{layout:?}");
+    }
+}
+
+#[test]
+fn slots_fit_the_page_margins_for_every_accepted_padding() {
+    for card_size in test_card_sizes() {
+        let max_padding = GridLayout::fit(card_size, 0.0).max_padding().min(50.0);
+        for step in 0..=100 {
+            let padding = max_padding * step as f64 / 100.0;
+            for layout in [
+                GridLayout::fit(card_size, padding),
+                GridLayout::single(card_size),
+            ] {
+                for rect in layout.slots() {
+                    assert_inside_margins(&layout, &rect);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn gutterfold_backs_are_behind_the_fronts() {
+    for card_size in test_card_sizes() {
+        let max_padding = GridLayout::gutterfold(card_size, 0.0)
+            .max_padding()
+            .min(50.0);
+        let layout = GridLayout::gutterfold(card_size, max_padding);
+        let page = GridLayout::fit(card_size, 0.0);
+        for front in layout.slots() {
+            let back = layout.folded_slot(&front);
+            assert_inside_margins(&layout, &front);
+            assert_inside_margins(&page, &back);
+            assert!(back.x >= PAGE_X / 2.0 + PAGE_MARGIN_MM - 1e-9, "{back:?}");
+            // same distance from the fold line
+            assert!(
+                (PAGE_X / 2.0 - (front.x + front.width) - (back.x - PAGE_X / 2.0)).abs() < 1e-9
+            );
+        }
+    }
+}
+
+#[test]
+fn slots_fit_the_page_margins_for_too_large_padding() {
+    let layout = GridLayout {
+        padding: 50.0,
+        ..GridLayout::fit(CardSize::STANDARD, 0.0)
+    };
+    for rect in layout.slots() {
+        assert_inside_margins(&layout, &rect);
+    }
+}
+
+#[test]
+fn slots_do_not_overlap() {
+    for card_size in test_card_sizes() {
+        let layout = GridLayout::fit(card_size, 0.0);
+        let slots = layout.slots();
+        for (i, a) in slots.iter().enumerate() {
+            for b in &slots[i + 1..] {
+                let apart_x = a.x + a.width <= b.x + 1e-9 || b.x + b.width <= a.x + 1e-9;
+                let apart_y = a.y + a.height <= b.y + 1e-9 || b.y + b.height <= a.y + 1e-9;
+                assert!(apart_x || apart_y, "{a:?} overlaps {b:?}");
+            }
+        }
+    }
+}
+
+#[test]
+fn pages_hold_every_card_once() {
+    let layout = GridLayout::fit(CardSize::STANDARD, 2.0);
+    for card_count in 0..40 {
+        let pages = layout.pages(card_count);
+        assert_eq!(pages.iter().map(Vec::len).sum::<usize>(), card_count);
+        assert_eq!(pages.len(), card_count.div_ceil(layout.cards_per_page()));
+        assert!(pages.iter().all(|page| !page.is_empty()));
+    }
+}
+
+#[test]
+fn cut_marks_are_outside_the_cards() {
+    for (padding, mark_count) in [(0.0, 16), (4.0, 24)] {
+        let slots = GridLayout::fit(CardSize::STANDARD, padding).slots();
+        let marks = cut_marks(&slots, 1.0);
+        assert_eq!(marks.len(), mark_count);
+
+        for [from, to] in marks {
+            for (x, y) in [from, to] {
+                assert!((0.0..=PAGE_X).contains(&x) && (0.0..=PAGE_Y).contains(&y));
+                for slot in &slots {
+                    let inside_x = x > slot.x - 1.5 && x < slot.x + slot.width + 1.5;
+                    let inside_y = y > slot.y - 1.5 && y < slot.y + slot.height + 1.5;
+                    assert!(!(inside_x && inside_y), "{from:?} {to:?} in {slot:?}");
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,116 @@
+//! Nested PDF outline (bookmarks). printpdf only supports one flat bookmark per page,
+//! so the outline is added to the saved document with lopdf.
+
+use anyhow::{Context, Result};
+use printpdf::lopdf::{self, Dictionary, Object, ObjectId, StringFormat};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutlineEntry {
+    pub title: String,
+    /// Zero based page index
+    pub page: usize,
+    pub children: Vec<OutlineEntry>,
+}
+
+impl OutlineEntry {
+    pub fn new(title: impl Into<String>, page: usize) -> Self {
+        OutlineEntry {
+            title: title.into(),
+            page,
+            children: Vec::new(),
+        }
+    }
+}
+
+/// Replaces the outline of the PDF with the given entries.
+pub fn add_outline(pdf_bytes: Vec<u8>, entries: &[OutlineEntry]) -> Result<Vec<u8>> {
+    if entries.is_empty() {
+        return Ok(pdf_bytes);
+    }
+
+    let mut doc = lopdf::Document::load_mem(&pdf_bytes).context("Failed to read created PDF")?;
+    let pages: Vec<ObjectId> = doc.get_pages().into_values().collect();
+
+    let outlines_id = doc.new_object_id();
+    let (first, last) = add_entries(&mut doc, &pages, outlines_id, entries);
+    let mut outlines = Dictionary::new();
+    outlines.set("Type", Object::Name(b"Outlines".to_vec()));
+    outlines.set("First", Object::Reference(first));
+    outlines.set("Last", Object::Reference(last));
+    // only the top level is visible, all entries start collapsed
+    outlines.set("Count", Object::Integer(entries.len() as i64));
+    doc.objects
+        .insert(outlines_id, Object::Dictionary(outlines));
+
+    let catalog_id = doc
+        .trailer
+        .get(b"Root")
+        .and_then(Object::as_reference)
+        .context("PDF has no catalog")?;
+    let catalog = doc
+        .get_object_mut(catalog_id)
+        .and_then(Object::as_dict_mut)
+        .context("PDF has no catalog")?;
+    let empty_outlines = catalog.get(b"Outlines").and_then(Object::as_reference).ok();
+    catalog.set("Outlines", Object::Reference(outlines_id));
+    catalog.set("PageMode", Object::Name(b"UseOutlines".to_vec()));
+    if let Some(empty_outlines) = empty_outlines {
+        doc.objects.remove(&empty_outlines);
+    }
+
+    let mut bytes = Vec::new();
+    doc.save_to(&mut bytes)
+        .context("Failed to write PDF outline")?;
+    Ok(bytes)
+}
+
+// Adds the entries as siblings below `parent`, returns the first and last object ids
+fn add_entries(
+    doc: &mut lopdf::Document,
+    pages: &[ObjectId],
+    parent: ObjectId,
+    entries: &[OutlineEntry],
+) -> (ObjectId, ObjectId) {
+    let ids: Vec<ObjectId> = entries.iter().map(|_| doc.new_object_id()).collect();
+
+    for (i, entry) in entries.iter().enumerate() {
+        let mut item = Dictionary::new();
+        item.set("Title", pdf_text_string(&entry.title));
+        item.set("Parent", Object::Reference(parent));
+        if let Some(page) = pages.get(entry.page).or(pages.last()) {
+            item.set(
+                "Dest",
+                Object::Array(vec![
+                    Object::Reference(*page),
+                    Object::Name(b"Fit".to_vec()),
+                ]),
+            );
+        }
+        if i > 0 {
+            item.set("Prev", Object::Reference(ids[i - 1]));
+        }
+        if let Some(next) = ids.get(i + 1) {
+            item.set("Next", Object::Reference(*next));
+        }
+        if !entry.children.is_empty() {
+            let (first, last) = add_entries(doc, pages, ids[i], &entry.children);
+            item.set("First", Object::Reference(first));
+            item.set("Last", Object::Reference(last));
+            // negative count: the entry starts collapsed
+            item.set("Count", Object::Integer(-(entry.children.len() as i64)));
+        }
+        doc.objects.insert(ids[i], Object::Dictionary(item));
+    }
+
+    (ids[0], ids[ids.len() - 1])
+}
+
+// PDF text strings are either PDFDocEncoding or UTF-16BE with a byte order mark
+fn pdf_text_string(text: &str) -> Object {
+    if text.is_ascii() {
+        return Object::String(text.as_bytes().to_vec(), StringFormat::Literal);
+    }
+    let mut bytes = vec![0xFE, 0xFF];
+    bytes.extend(text.encode_utf16().flat_map(|unit| unit.to_be_bytes()));
+    Object::String(bytes, StringFormat::Hexadecimal)
+}
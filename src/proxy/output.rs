@@ -0,0 +1,250 @@
+//! Location, file name and format of the created PDF or page images.
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Result};
+use printpdf::OffsetDateTime;
+
+/// What happens if the PDF file already exists.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ConflictPolicy {
+    Overwrite,
+    /// Appends " (2)", " (3)", ... to the file name
+    #[default]
+    NumberSuffix,
+    /// Lets the user decide, creating the PDF fails if the file exists and wasn't confirmed
+    Ask,
+}
+
+impl ConflictPolicy {
+    pub const ALL: [ConflictPolicy; 3] = [
+        ConflictPolicy::Overwrite,
+        ConflictPolicy::NumberSuffix,
+        ConflictPolicy::Ask,
+    ];
+}
+
+impl std::fmt::Display for ConflictPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let label = match self {
+            ConflictPolicy::Overwrite => "Overwrite existing file",
+            ConflictPolicy::NumberSuffix => "Add number to file name",
+            ConflictPolicy::Ask => "Ask before overwriting",
+        };
+        f.write_str(label)
+    }
+}
+
+/// File format of the output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutputFormat {
+    #[default]
+    Pdf,
+    /// One numbered image per page, for photo kiosks and print services that only take images
+    Png,
+    Jpeg,
+}
+
+impl OutputFormat {
+    pub const ALL: [OutputFormat; 3] = [OutputFormat::Pdf, OutputFormat::Png, OutputFormat::Jpeg];
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            OutputFormat::Pdf => "pdf",
+            OutputFormat::Png => "png",
+            OutputFormat::Jpeg => "jpg",
+        }
+    }
+
+    /// Whether each page is written to its own image file
+    pub fn is_image(self) -> bool {
+        self != OutputFormat::Pdf
+    }
+}
+
+impl std::fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let label = match self {
+            OutputFormat::Pdf => "PDF",
+            OutputFormat::Png => "PNG images (one per page)",
+            OutputFormat::Jpeg => "JPEG images (one per page)",
+        };
+        f.write_str(label)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct OutputSettings {
+    /// Folder of the PDF, the decklist's folder if not set
+    pub dir: Option<PathBuf>,
+    /// File name with `{deck}`, `{layout}` and `{date}` placeholders
+    pub file_name_template: String,
+    pub conflict_policy: ConflictPolicy,
+    pub format: OutputFormat,
+    /// Resolution of the page images
+    pub image_dpi: f64,
+}
+
+impl Default for OutputSettings {
+    fn default() -> Self {
+        OutputSettings {
+            dir: None,
+            file_name_template: "{deck}.pdf".to_string(),
+            conflict_policy: ConflictPolicy::default(),
+            format: OutputFormat::default(),
+            image_dpi: 300.0,
+        }
+    }
+}
+
+impl OutputSettings {
+    /// Output path for the decklist, before applying the conflict policy. Page images are
+    /// numbered after its file name.
+    pub fn target_path(&self, decklist_path: &Path, layout: &str) -> PathBuf {
+        let deck = decklist_path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("output");
+        let mut file_name = self
+            .file_name_template
+            .replace("{deck}", deck)
+            .replace("{layout}", layout)
+            .replace("{date}", &today());
+        // the template may still end in the extension of another format
+        if let Some((stem, extension)) = file_name.rsplit_once('.') {
+            if ["pdf", "png", "jpg", "jpeg"].contains(&extension.to_lowercase().as_str()) {
+                file_name.truncate(stem.len());
+            }
+        }
+        file_name.push('.');
+        file_name.push_str(self.format.extension());
+
+        let dir = match &self.dir {
+            Some(dir) => dir.as_path(),
+            None => decklist_path.parent().unwrap_or(Path::new("")),
+        };
+        dir.join(file_name)
+    }
+
+    /// Output path for the decklist with the conflict policy applied.
+    pub(super) fn resolve_path(&self, decklist_path: &Path, layout: &str) -> Result<PathBuf> {
+        let path = self.target_path(decklist_path, layout);
+        if !self.first_file(&path).exists() {
+            return Ok(path);
+        }
+
+        match self.conflict_policy {
+            ConflictPolicy::Overwrite => Ok(path),
+            ConflictPolicy::NumberSuffix => Ok(self.numbered_path(&path)),
+            ConflictPolicy::Ask => bail!("'{}' already exists", self.first_file(&path).display()),
+        }
+    }
+
+    /// The PDF itself or the image of the first page, which decides whether an output path
+    /// is taken.
+    pub fn first_file(&self, path: &Path) -> PathBuf {
+        match self.format {
+            OutputFormat::Pdf => path.to_path_buf(),
+            _ => page_path(path, 1),
+        }
+    }
+
+    // First free "name (n).pdf" next to the given path
+    fn numbered_path(&self, path: &Path) -> PathBuf {
+        let stem = path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("output");
+
+        (2..)
+            .map(|n| path.with_file_name(format!("{} ({}).{}", stem, n, self.format.extension())))
+            .find(|candidate| !self.first_file(candidate).exists())
+            .unwrap()
+    }
+}
+
+/// Image file of the page with the one based number, e.g. "deck-03.png" for "deck.png"
+pub(super) fn page_path(path: &Path, page: usize) -> PathBuf {
+    let stem = path
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("output");
+    let extension = path.extension().and_then(|s| s.to_str()).unwrap_or("png");
+    path.with_file_name(format!("{}-{:02}.{}", stem, page, extension))
+}
+
+/// Local date as YYYY-MM-DD
+pub(super) fn today() -> String {
+    let date = OffsetDateTime::try_now_local()
+        .unwrap_or_else(|_| OffsetDateTime::now_utc())
+        .date();
+    format!("{}-{:02}-{:02}", date.year(), date.month(), date.day())
+}
+
+#[test]
+fn placeholders_are_filled_in_and_the_extension_follows_the_format() {
+    let settings = OutputSettings {
+        file_name_template: "{deck} {layout} {date}.pdf".to_string(),
+        ..OutputSettings::default()
+    };
+    let decklist = Path::new("decks").join("Burn.txt");
+
+    assert_eq!(
+        settings.target_path(&decklist, "grid"),
+        Path::new("decks").join(format!("Burn grid {}.pdf", today()))
+    );
+
+    let png = OutputSettings {
+        format: OutputFormat::Png,
+        dir: Some(PathBuf::from("out")),
+        ..settings
+    };
+    assert_eq!(
+        png.target_path(&decklist, "grid"),
+        Path::new("out").join(format!("Burn grid {}.png", today()))
+    );
+    assert_eq!(
+        png.first_file(&png.target_path(&decklist, "grid")),
+        Path::new("out").join(format!("Burn grid {}-01.png", today()))
+    );
+}
+
+#[test]
+fn existing_files_are_numbered_or_refused() {
+    let dir = std::env::temp_dir().join(format!("proxy-output-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let decklist = dir.join("Burn.txt");
+    let settings = OutputSettings {
+        conflict_policy: ConflictPolicy::NumberSuffix,
+        ..OutputSettings::default()
+    };
+
+    assert_eq!(
+        settings.resolve_path(&decklist, "grid").unwrap(),
+        dir.join("Burn.pdf")
+    );
+
+    std::fs::write(dir.join("Burn.pdf"), "").unwrap();
+    std::fs::write(dir.join("Burn (2).pdf"), "").unwrap();
+    assert_eq!(
+        settings.resolve_path(&decklist, "grid").unwrap(),
+        dir.join("Burn (3).pdf")
+    );
+
+    let overwrite = OutputSettings {
+        conflict_policy: ConflictPolicy::Overwrite,
+        ..settings.clone()
+    };
+    assert_eq!(
+        overwrite.resolve_path(&decklist, "grid").unwrap(),
+        dir.join("Burn.pdf")
+    );
+
+    let ask = OutputSettings {
+        conflict_policy: ConflictPolicy::Ask,
+        ..settings
+    };
+    assert!(ask.resolve_path(&decklist, "grid").is_err());
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,267 @@
+//! Page images for photo kiosks and print services that only take images. The pages are the
+//! same sheets as in the PDF, drawn into pixels at the chosen DPI.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use image::codecs::jpeg::{JpegEncoder, PixelDensity};
+use image::codecs::png::PngEncoder;
+use image::{imageops, ColorType, DynamicImage, GrayImage, ImageEncoder, Rgb, RgbImage};
+use printpdf::{ColorSpace, ImageFilter, ImageXObject};
+
+use super::output::{page_path, OutputFormat};
+use super::{
+    card_placement, cut_marks, fold_line, format_file_size, ProxyOptions, Sheet,
+    CUT_MARK_THICKNESS, FOLD_LINE_DASH, FOLD_LINE_THICKNESS, MM_PER_INCH, PAGE_X, PAGE_Y,
+};
+
+const PT_PER_INCH: f64 = 72.0;
+// Kiosk prints are made from the images as they are, so they get little compression
+const PAGE_JPEG_QUALITY: u8 = 95;
+
+/// Writes every sheet to a numbered image next to `path`. Returns the image of the first page.
+pub(super) fn save_page_images(
+    path: &Path,
+    sheets: &[Sheet],
+    options: &ProxyOptions,
+) -> Result<PathBuf> {
+    if sheets.is_empty() {
+        bail!("There are no card images to save");
+    }
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create output folder '{}'", dir.display()))?;
+    }
+
+    let dpi = options.output.image_dpi;
+    for (page, sheet) in sheets.iter().enumerate() {
+        let image = render_sheet(sheet, options, dpi)?;
+        let bytes = encode_page(&image, options.output.format, dpi)?;
+        let page_path = page_path(path, page + 1);
+        fs::write(&page_path, &bytes)
+            .with_context(|| format!("Failed to write '{}'", page_path.display()))?;
+        println!(
+            "Saved page image '{}' ({})",
+            page_path.display(),
+            format_file_size(bytes.len() as u64)
+        );
+    }
+    Ok(page_path(path, 1))
+}
+
+/// Draws the cards, fold line and cut marks of the sheet onto a white page.
+fn render_sheet(sheet: &Sheet, options: &ProxyOptions, dpi: f64) -> Result<RgbImage> {
+    let px_per_mm = dpi / MM_PER_INCH;
+    let mut page = RgbImage::from_pixel(
+        (PAGE_X * px_per_mm).round() as u32,
+        (PAGE_Y * px_per_mm).round() as u32,
+        Rgb([u8::MAX; 3]),
+    );
+
+    if sheet.fold_line {
+        draw_line(
+            &mut page,
+            fold_line(options),
+            FOLD_LINE_THICKNESS,
+            Some(FOLD_LINE_DASH),
+            dpi,
+        );
+    }
+    if options.cut_marks {
+        for mark in cut_marks(&sheet.slots(), options) {
+            draw_line(&mut page, mark, CUT_MARK_THICKNESS, None, dpi);
+        }
+    }
+
+    for card in &sheet.cards {
+        let pixels = pixels_of(&card.image.image)?;
+        let placement = card_placement(pixels.width() > pixels.height(), &card.slot, options);
+        let pixels = match (placement.rotated, placement.width > placement.height) {
+            (true, true) => pixels.rotate90(),
+            (true, false) => pixels.rotate270(),
+            (false, _) => pixels,
+        };
+
+        // rounding both edges keeps neighbouring cards without padding free of gaps
+        let left = (placement.x * px_per_mm).round();
+        let right = ((placement.x + placement.width) * px_per_mm).round();
+        let top = ((PAGE_Y - placement.y - placement.height) * px_per_mm).round();
+        let bottom = ((PAGE_Y - placement.y) * px_per_mm).round();
+        let width = (right - left).max(1.0) as u32;
+        let height = (bottom - top).max(1.0) as u32;
+
+        let card_pixels = pixels
+            .resize_exact(width, height, imageops::FilterType::Lanczos3)
+            .to_rgb8();
+        imageops::replace(&mut page, &card_pixels, left as i64, top as i64);
+    }
+
+    Ok(page)
+}
+
+/// Pixels of a card image, as they would be shown in the PDF
+fn pixels_of(image: &ImageXObject) -> Result<DynamicImage> {
+    if matches!(image.image_filter, Some(ImageFilter::DCT)) {
+        return image::load_from_memory(&image.image_data).context("Failed to decode card image");
+    }
+
+    let (width, height) = (image.width.0 as u32, image.height.0 as u32);
+    let pixels = match image.color_space {
+        ColorSpace::Rgb => {
+            RgbImage::from_raw(width, height, image.image_data.clone()).map(DynamicImage::ImageRgb8)
+        }
+        ColorSpace::Greyscale => GrayImage::from_raw(width, height, image.image_data.clone())
+            .map(DynamicImage::ImageLuma8),
+        _ => None,
+    };
+    pixels.context("Page images need RGB or greyscale card images")
+}
+
+/// Draws a straight line between two points in mm from the bottom left page corner, with its
+/// thickness and dash length in pt like the lines of the PDF.
+fn draw_line(
+    page: &mut RgbImage,
+    [from, to]: [(f64, f64); 2],
+    thickness_pt: f64,
+    dash_pt: Option<i64>,
+    dpi: f64,
+) {
+    let px_per_mm = dpi / MM_PER_INCH;
+    let to_px = |(x, y): (f64, f64)| (x * px_per_mm, (PAGE_Y - y) * px_per_mm);
+    let ((x0, y0), (x1, y1)) = (to_px(from), to_px(to));
+    let half_thickness = (thickness_pt / PT_PER_INCH * dpi).max(1.0) / 2.0;
+    let dash = dash_pt.map(|dash| dash as f64 / PT_PER_INCH * dpi);
+    let length = (x1 - x0).hypot(y1 - y0);
+
+    for step in 0..=length.round() as u32 {
+        let distance = step as f64;
+        if dash.is_some_and(|dash| (distance / dash) as u32 % 2 == 1) {
+            continue;
+        }
+        let t = if length > 0.0 { distance / length } else { 0.0 };
+        let (x, y) = (x0 + (x1 - x0) * t, y0 + (y1 - y0) * t);
+
+        // square pen, at least one pixel wide
+        let (left, top) = ((x - half_thickness).round(), (y - half_thickness).round());
+        let right = (x + half_thickness).round().max(left + 1.0);
+        let bottom = (y + half_thickness).round().max(top + 1.0);
+        for py in top as i64..bottom as i64 {
+            for px in left as i64..right as i64 {
+                if px >= 0 && py >= 0 && px < page.width() as i64 && py < page.height() as i64 {
+                    page.put_pixel(px as u32, py as u32, Rgb([0, 0, 0]));
+                }
+            }
+        }
+    }
+}
+
+fn encode_page(page: &RgbImage, format: OutputFormat, dpi: f64) -> Result<Vec<u8>> {
+    let mut bytes = Vec::new();
+    match format {
+        OutputFormat::Png => {
+            PngEncoder::new(&mut bytes)
+                .write_image(page.as_raw(), page.width(), page.height(), ColorType::Rgb8)
+                .context("Failed to encode page as PNG")?;
+            Ok(png_with_dpi(bytes, dpi))
+        }
+        OutputFormat::Jpeg => {
+            let mut encoder = JpegEncoder::new_with_quality(&mut bytes, PAGE_JPEG_QUALITY);
+            encoder.set_pixel_density(PixelDensity::dpi(dpi.round() as u16));
+            encoder
+                .encode_image(page)
+                .context("Failed to encode page as JPEG")?;
+            Ok(bytes)
+        }
+        OutputFormat::Pdf => bail!("PDFs are not saved as page images"),
+    }
+}
+
+/// Adds the resolution to a PNG, so the pages print at their real size. The PNG encoder
+/// doesn't write it, the `pHYs` chunk goes right after the header chunk.
+fn png_with_dpi(png: Vec<u8>, dpi: f64) -> Vec<u8> {
+    // 8 bytes signature and the 25 bytes of the IHDR chunk
+    const HEADER_END: usize = 33;
+    let pixels_per_meter = (dpi / MM_PER_INCH * 1000.0).round() as u32;
+
+    let mut chunk_data = b"pHYs".to_vec();
+    chunk_data.extend(pixels_per_meter.to_be_bytes());
+    chunk_data.extend(pixels_per_meter.to_be_bytes());
+    // unit: meter
+    chunk_data.push(1);
+
+    let mut chunk = (chunk_data.len() as u32 - 4).to_be_bytes().to_vec();
+    chunk.extend(&chunk_data);
+    chunk.extend(crc32(&chunk_data).to_be_bytes());
+
+    let mut with_dpi = png;
+    with_dpi.splice(HEADER_END..HEADER_END, chunk);
+    with_dpi
+}
+
+// CRC-32 of PNG chunks, over the chunk type and data
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = u32::MAX;
+    for &byte in bytes {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+#[test]
+fn cards_and_cut_marks_are_drawn_at_their_page_position() {
+    use super::layout::Rect;
+    use super::PlacedCard;
+
+    let red = DynamicImage::ImageRgb8(RgbImage::from_pixel(63, 88, Rgb([200, 0, 0])));
+    let slot = Rect {
+        x: 20.0,
+        y: 100.0,
+        width: 63.0,
+        height: 88.0,
+    };
+    let sheet = Sheet {
+        cards: vec![PlacedCard {
+            slot,
+            image: std::sync::Arc::new(printpdf::Image::from_dynamic_image(&red)),
+            label: None,
+            front: true,
+        }],
+        fold_line: false,
+    };
+    let options = ProxyOptions {
+        cut_marks: true,
+        ..ProxyOptions::default()
+    };
+
+    // 1 pixel per mm, y counted from the top of the page
+    let page = render_sheet(&sheet, &options, MM_PER_INCH).unwrap();
+    assert_eq!(page.dimensions(), (210, 297));
+    let top = (PAGE_Y - slot.y - slot.height) as u32;
+    assert_eq!(page.get_pixel(21, top + 1).0, [200, 0, 0]);
+    assert_eq!(page.get_pixel(82, top + 87).0, [200, 0, 0]);
+    assert_eq!(page.get_pixel(19, top + 1).0, [255, 255, 255]);
+    assert_eq!(page.get_pixel(21, top - 1).0, [255, 255, 255]);
+    // the cut mark in line with the left card edge starts 1 mm above the card
+    assert_eq!(page.get_pixel(20, top - 2).0, [0, 0, 0]);
+}
+
+#[test]
+fn png_pages_have_their_resolution() {
+    let page = RgbImage::from_pixel(4, 4, Rgb([10, 20, 30]));
+    let png = encode_page(&page, OutputFormat::Png, 300.0).unwrap();
+
+    let phys = png.windows(4).position(|window| window == b"pHYs").unwrap();
+    // 300 DPI are 11811 pixels per meter
+    assert_eq!(png[phys + 4..phys + 8], 11811u32.to_be_bytes());
+    // the decoder checks the CRC of every chunk
+    let decoded = image::load_from_memory(&png).unwrap().to_rgb8();
+    assert_eq!(decoded, page);
+}
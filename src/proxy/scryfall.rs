@@ -0,0 +1,437 @@
+//! Typed card objects of the Scryfall API, see <https://scryfall.com/docs/api/cards>.
+//! Only the fields the proxies use are deserialized, all others are ignored.
+
+use anyhow::{bail, Result};
+use serde::Deserialize;
+
+use super::CardImageUrls;
+
+/// Physical layout of a card, the `layout` field of Scryfall cards
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Layout {
+    #[default]
+    Normal,
+    Split,
+    Flip,
+    Transform,
+    ModalDfc,
+    Meld,
+    Leveler,
+    Class,
+    Case,
+    Saga,
+    Adventure,
+    Mutate,
+    Prototype,
+    Battle,
+    Planar,
+    Scheme,
+    Vanguard,
+    Token,
+    DoubleFacedToken,
+    Emblem,
+    Augment,
+    Host,
+    ArtSeries,
+    ReversibleCard,
+    /// Layouts added to Scryfall after this list was written
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct Card {
+    #[allow(dead_code)] // unread, identifies the printing for reports
+    pub id: String,
+    /// Missing on reversible cards, each of their faces has its own
+    #[allow(dead_code)] // unread, groups reprints for reports
+    pub oracle_id: Option<String>,
+    pub name: String,
+    pub set: String,
+    pub collector_number: String,
+    #[allow(dead_code)] // unread, for reports
+    pub lang: String,
+    pub layout: Layout,
+    /// Missing on cards with a separate image per face
+    pub image_uris: Option<ImageUris>,
+    #[serde(default)]
+    pub card_faces: Vec<CardFace>,
+    /// Tokens, meld parts and other cards related to this one
+    #[serde(default)]
+    pub all_parts: Vec<RelatedCard>,
+    #[allow(dead_code)] // unread, for deck price reports
+    #[serde(default)]
+    pub prices: Prices,
+    #[allow(dead_code)] // unread, for text proxies
+    pub mana_cost: Option<String>,
+    pub type_line: Option<String>,
+    #[allow(dead_code)] // unread, for text proxies
+    pub oracle_text: Option<String>,
+    #[serde(default)]
+    pub color_identity: Vec<String>,
+    /// Mana value, missing on reversible cards
+    #[serde(default)]
+    pub cmc: f64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct CardFace {
+    #[allow(dead_code)] // unread, for text proxies
+    pub name: String,
+    /// Only set on the faces of reversible cards
+    #[allow(dead_code)] // unread, groups reprints for reports
+    pub oracle_id: Option<String>,
+    #[allow(dead_code)] // unread, for text proxies
+    pub mana_cost: Option<String>,
+    pub type_line: Option<String>,
+    #[allow(dead_code)] // unread, for text proxies
+    pub oracle_text: Option<String>,
+    /// Only set if the faces are printed on different sides
+    pub image_uris: Option<ImageUris>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct ImageUris {
+    pub small: Option<String>,
+    pub normal: Option<String>,
+    pub large: Option<String>,
+    pub png: Option<String>,
+    pub art_crop: Option<String>,
+    pub border_crop: Option<String>,
+}
+
+impl ImageUris {
+    /// URL of the image version with the given Scryfall name, e.g. "png"
+    pub fn get(&self, key: &str) -> Option<&str> {
+        let url = match key {
+            "small" => &self.small,
+            "normal" => &self.normal,
+            "large" => &self.large,
+            "png" => &self.png,
+            "art_crop" => &self.art_crop,
+            "border_crop" => &self.border_crop,
+            _ => &None,
+        };
+        url.as_deref()
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct RelatedCard {
+    pub id: String,
+    /// "token", "meld_part", "meld_result" or "combo_piece"
+    pub component: String,
+    pub name: String,
+    #[allow(dead_code)] // unread, for token reports
+    pub type_line: String,
+}
+
+/// Prices as decimal strings, missing if Scryfall has no price
+#[allow(dead_code)] // unread, for deck price reports
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct Prices {
+    pub usd: Option<String>,
+    pub usd_foil: Option<String>,
+    pub eur: Option<String>,
+    pub tix: Option<String>,
+}
+
+impl Card {
+    /// Image URLs of the front and back face in the given image version. The back of meld
+    /// cards is a different card, see [`Card::meld_result`].
+    pub(super) fn image_urls(&self, image_file_type: &str) -> Result<CardImageUrls> {
+        let card_image = self.card_image(image_file_type);
+        let (front, back) = match self.layout {
+            // One image with all faces of split, flip and adventure cards on it
+            Layout::Normal
+            | Layout::Split
+            | Layout::Flip
+            | Layout::Adventure
+            | Layout::Meld
+            | Layout::Leveler
+            | Layout::Class
+            | Layout::Case
+            | Layout::Saga
+            | Layout::Mutate
+            | Layout::Prototype
+            | Layout::Planar
+            | Layout::Scheme
+            | Layout::Vanguard
+            | Layout::Token
+            | Layout::Emblem
+            | Layout::Augment
+            | Layout::Host => (
+                card_image.or_else(|| self.face_image(0, image_file_type)),
+                None,
+            ),
+            // An image for each side, faces without their own image use the card image
+            Layout::Transform
+            | Layout::ModalDfc
+            | Layout::Battle
+            | Layout::DoubleFacedToken
+            | Layout::ArtSeries
+            | Layout::ReversibleCard => (
+                self.face_image(0, image_file_type).or(card_image),
+                self.face_image(1, image_file_type),
+            ),
+            Layout::Unknown => match card_image {
+                Some(front) => (Some(front), None),
+                None => (
+                    self.face_image(0, image_file_type),
+                    self.face_image(1, image_file_type),
+                ),
+            },
+        };
+
+        match front {
+            Some(front) => Ok(CardImageUrls {
+                front: Some(front),
+                back,
+            }),
+            None => bail!(
+                "No '{}' image found for '{}' with layout {:?}",
+                image_file_type,
+                self.name,
+                self.layout
+            ),
+        }
+    }
+
+    fn card_image(&self, image_file_type: &str) -> Option<String> {
+        let image_uris = self.image_uris.as_ref()?;
+        image_uris.get(image_file_type).map(String::from)
+    }
+
+    fn face_image(&self, face: usize, image_file_type: &str) -> Option<String> {
+        let image_uris = self.card_faces.get(face)?.image_uris.as_ref()?;
+        image_uris.get(image_file_type).map(String::from)
+    }
+
+    /// The card the meld parts combine into, whose image is their back. `None` for cards
+    /// that don't meld and for the melded card itself.
+    pub(super) fn meld_result(&self) -> Option<&RelatedCard> {
+        if self.layout != Layout::Meld {
+            return None;
+        }
+        self.all_parts
+            .iter()
+            .find(|part| part.component == "meld_result" && part.name != self.name)
+    }
+
+    /// Planes and schemes are printed on oversized cards
+    pub(super) fn is_oversized(&self) -> bool {
+        matches!(self.layout, Layout::Planar | Layout::Scheme)
+    }
+
+    /// Split cards and battles are read sideways and printed in landscape slots
+    pub(super) fn is_landscape(&self) -> bool {
+        matches!(self.layout, Layout::Split | Layout::Battle)
+    }
+
+    /// Type line of the front face
+    pub(super) fn front_type_line(&self) -> &str {
+        let type_line = self
+            .type_line
+            .as_deref()
+            .or_else(|| self.card_faces.first()?.type_line.as_deref())
+            .unwrap_or_default();
+        type_line.split(" // ").next().unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+macro_rules! scryfall_fixture {
+    ($name:literal) => {
+        include_str!(concat!("../../tests/fixtures/scryfall/", $name, ".json"))
+    };
+}
+
+// Scryfall card objects for each layout, trimmed to a few fields besides the modeled ones
+#[cfg(test)]
+const FIXTURES: [(Layout, &str); 24] = [
+    (Layout::Normal, scryfall_fixture!("normal")),
+    (Layout::Split, scryfall_fixture!("split")),
+    (Layout::Flip, scryfall_fixture!("flip")),
+    (Layout::Transform, scryfall_fixture!("transform")),
+    (Layout::ModalDfc, scryfall_fixture!("modal_dfc")),
+    (Layout::Meld, scryfall_fixture!("meld")),
+    (Layout::Leveler, scryfall_fixture!("leveler")),
+    (Layout::Class, scryfall_fixture!("class")),
+    (Layout::Case, scryfall_fixture!("case")),
+    (Layout::Saga, scryfall_fixture!("saga")),
+    (Layout::Adventure, scryfall_fixture!("adventure")),
+    (Layout::Mutate, scryfall_fixture!("mutate")),
+    (Layout::Prototype, scryfall_fixture!("prototype")),
+    (Layout::Battle, scryfall_fixture!("battle")),
+    (Layout::Planar, scryfall_fixture!("planar")),
+    (Layout::Scheme, scryfall_fixture!("scheme")),
+    (Layout::Vanguard, scryfall_fixture!("vanguard")),
+    (Layout::Token, scryfall_fixture!("token")),
+    (
+        Layout::DoubleFacedToken,
+        scryfall_fixture!("double_faced_token"),
+    ),
+    (Layout::Emblem, scryfall_fixture!("emblem")),
+    (Layout::Augment, scryfall_fixture!("augment")),
+    (Layout::Host, scryfall_fixture!("host")),
+    (Layout::ArtSeries, scryfall_fixture!("art_series")),
+    (Layout::ReversibleCard, scryfall_fixture!("reversible_card")),
+];
+
+#[cfg(test)]
+pub(super) fn fixture(layout: Layout) -> Card {
+    let (_, json) = FIXTURES
+        .iter()
+        .find(|(fixture_layout, _)| *fixture_layout == layout)
+        .unwrap();
+    serde_json::from_str(json).unwrap()
+}
+
+#[test]
+fn every_layout_fixture_deserializes() {
+    for (layout, json) in FIXTURES {
+        let card: Card = serde_json::from_str(json)
+            .unwrap_or_else(|e| panic!("{:?} fixture doesn't deserialize: {}", layout, e));
+        assert_eq!(card.layout, layout);
+        assert!(!card.id.is_empty() && !card.name.is_empty() && !card.set.is_empty());
+        assert_eq!(card.lang, "en");
+        assert!(
+            card.image_uris.is_some() || card.card_faces.iter().all(|f| f.image_uris.is_some()),
+            "{:?} has no images",
+            layout
+        );
+    }
+}
+
+#[test]
+fn single_image_cards_have_only_a_front() {
+    for layout in [
+        Layout::Normal,
+        Layout::Split,
+        Layout::Flip,
+        Layout::Adventure,
+    ] {
+        let urls = fixture(layout).image_urls("png").unwrap();
+        assert!(urls.front.unwrap().contains("/png/front/"));
+        assert_eq!(urls.back, None, "{:?}", layout);
+    }
+}
+
+#[test]
+fn double_faced_cards_have_a_front_and_a_back() {
+    for layout in [
+        Layout::Transform,
+        Layout::ModalDfc,
+        Layout::Battle,
+        Layout::DoubleFacedToken,
+    ] {
+        let urls = fixture(layout).image_urls("large").unwrap();
+        assert!(urls.front.unwrap().contains("/large/front/"));
+        assert!(urls.back.unwrap().contains("/large/back/"), "{:?}", layout);
+    }
+}
+
+#[test]
+fn faces_and_parts_are_deserialized() {
+    let split = fixture(Layout::Split);
+    assert_eq!(split.card_faces.len(), 2);
+    assert_eq!(split.card_faces[1].name, "Ice");
+    assert_eq!(split.mana_cost.as_deref(), Some("{1}{R} // {1}{U}"));
+    assert_eq!(split.cmc, 4.0);
+
+    let meld = fixture(Layout::Meld);
+    let meld_result = meld
+        .all_parts
+        .iter()
+        .find(|part| part.component == "meld_result")
+        .unwrap();
+    assert_eq!(meld_result.name, "Brisela, Voice of Nightmares");
+
+    let normal = fixture(Layout::Normal);
+    assert_eq!(normal.prices.usd.as_deref(), Some("1.12"));
+    assert_eq!(normal.prices.usd_foil.as_deref(), Some("9.80"));
+    assert_eq!(
+        normal.oracle_text.as_deref(),
+        Some("Lightning Bolt deals 3 damage to any target.")
+    );
+}
+
+#[test]
+fn reversible_cards_have_oracle_data_on_their_faces() {
+    let card = fixture(Layout::ReversibleCard);
+    assert_eq!(card.oracle_id, None);
+    assert_eq!(card.type_line, None);
+    assert!(card.card_faces.iter().all(|face| face.oracle_id.is_some()));
+    assert_eq!(card.front_type_line(), "Legendary Creature — Homunculus");
+}
+
+#[test]
+fn unknown_layouts_are_accepted() {
+    let json = FIXTURES[0]
+        .1
+        .replace("\"layout\": \"normal\"", "\"layout\": \"future_layout\"");
+    let card: Card = serde_json::from_str(&json).unwrap();
+    assert_eq!(card.layout, Layout::Unknown);
+}
+
+#[test]
+fn faces_without_images_use_the_card_image() {
+    let mut card = fixture(Layout::Transform);
+    let card_image = card.card_faces[0].image_uris.take();
+    card.image_uris = card_image;
+
+    let urls = card.image_urls("large").unwrap();
+
+    assert!(urls.front.unwrap().contains("/large/front/"));
+    assert!(urls.back.unwrap().contains("/large/back/"));
+}
+
+#[test]
+fn reversible_and_art_series_cards_use_both_faces() {
+    for layout in [Layout::ReversibleCard, Layout::ArtSeries] {
+        let urls = fixture(layout).image_urls("normal").unwrap();
+        assert!(urls.front.unwrap().contains("/normal/front/"));
+        assert!(urls.back.unwrap().contains("/normal/back/"), "{:?}", layout);
+    }
+}
+
+#[test]
+fn every_layout_has_a_front_image() {
+    for (layout, json) in FIXTURES {
+        let card: Card = serde_json::from_str(json).unwrap();
+        for key in ["small", "normal", "large", "png", "border_crop"] {
+            let urls = card.image_urls(key).unwrap();
+            assert!(urls.front.is_some(), "{:?} {}", layout, key);
+        }
+    }
+}
+
+#[test]
+fn only_meld_parts_have_a_meld_result() {
+    let bruna = fixture(Layout::Meld);
+    assert_eq!(
+        bruna.meld_result().map(|part| part.name.as_str()),
+        Some("Brisela, Voice of Nightmares")
+    );
+
+    let brisela = Card {
+        name: "Brisela, Voice of Nightmares".to_string(),
+        ..bruna.clone()
+    };
+    assert_eq!(brisela.meld_result(), None);
+    assert_eq!(fixture(Layout::Normal).meld_result(), None);
+}
+
+#[test]
+fn planes_and_schemes_are_oversized() {
+    for (layout, json) in FIXTURES {
+        let card: Card = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            card.is_oversized(),
+            matches!(layout, Layout::Planar | Layout::Scheme),
+            "{:?}",
+            layout
+        );
+    }
+}
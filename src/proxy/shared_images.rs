@@ -0,0 +1,247 @@
+//! Card images that are embedded once into the PDF and drawn by name on every page they are
+//! placed on. printpdf would embed its own copy of the pixels for every placement.
+
+use std::cell::RefCell;
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use printpdf::lopdf::{self, content::Operation, Dictionary, Object, ObjectId};
+use printpdf::{CurTransMat, Image, ImageTransform, Mm, PdfLayerReference};
+
+// Prefix of the image names, printpdf names its own images "X" and a number
+const IMAGE_NAME_PREFIX: &str = "Card";
+
+/// The images placed into a document, in the order they were first placed.
+#[derive(Default)]
+pub(super) struct SharedImages {
+    images: RefCell<Vec<Arc<Image>>>,
+}
+
+impl SharedImages {
+    /// Draws the image with the transform, an image that was placed before is drawn by the
+    /// name it got then.
+    pub(super) fn place(
+        &self,
+        layer: &PdfLayerReference,
+        image: Arc<Image>,
+        transform: ImageTransform,
+    ) {
+        let mut images = self.images.borrow_mut();
+        let index = match images.iter().position(|placed| Arc::ptr_eq(placed, &image)) {
+            Some(index) => index,
+            None => {
+                images.push(Arc::clone(&image));
+                images.len() - 1
+            }
+        };
+
+        // the same transformations as printpdf applies to an image, which is a 1 x 1 square
+        // in the PDF
+        let dpi = transform.dpi.unwrap_or(300.0);
+        let width = image.image.width.into_pt(dpi).0 * transform.scale_x.unwrap_or(1.0);
+        let height = image.image.height.into_pt(dpi).0 * transform.scale_y.unwrap_or(1.0);
+        let mut transforms = vec![CurTransMat::Scale(width, height)];
+        if let Some(rotate) = &transform.rotate {
+            transforms.push(CurTransMat::Rotate(rotate.angle_ccw_degrees));
+        }
+        transforms.push(CurTransMat::Translate(
+            transform.translate_x.unwrap_or(Mm(0.0)).into_pt(),
+            transform.translate_y.unwrap_or(Mm(0.0)).into_pt(),
+        ));
+        let matrix = transforms
+            .into_iter()
+            .fold(CurTransMat::Identity, |matrix, transform| {
+                CurTransMat::Raw(CurTransMat::combine_matrix(matrix.into(), transform.into()))
+            });
+
+        layer.save_graphics_state();
+        layer.add_operation(matrix);
+        layer.add_operation(Operation::new(
+            "Do",
+            vec![Object::Name(image_name(index).into_bytes())],
+        ));
+        layer.restore_graphics_state();
+    }
+
+    /// Adds every placed image to the saved PDF and to the resources of the pages that draw
+    /// it.
+    pub(super) fn embed(self, pdf_bytes: Vec<u8>) -> Result<Vec<u8>> {
+        let images = self.images.into_inner();
+        if images.is_empty() {
+            return Ok(pdf_bytes);
+        }
+        let mut doc =
+            lopdf::Document::load_mem(&pdf_bytes).context("Failed to read created PDF")?;
+
+        let image_ids: Vec<ObjectId> = images
+            .into_iter()
+            .map(|image| {
+                // the card back is still held by the deck, only its pixels are copied
+                let image = Arc::try_unwrap(image)
+                    .map(|image| image.image)
+                    .unwrap_or_else(|shared| shared.image.clone());
+                let stream: lopdf::Stream = image.into();
+                doc.add_object(stream)
+            })
+            .collect();
+
+        for page_id in doc.get_pages().into_values().collect::<Vec<_>>() {
+            let content = doc.get_and_decode_page_content(page_id)?;
+            let mut names: Vec<usize> = content
+                .operations
+                .iter()
+                .filter(|operation| operation.operator == "Do")
+                .filter_map(|operation| operation.operands.first()?.as_name_str().ok())
+                .filter_map(|name| name.strip_prefix(IMAGE_NAME_PREFIX)?.parse().ok())
+                .collect();
+            names.sort_unstable();
+            names.dedup();
+            if names.is_empty() {
+                continue;
+            }
+
+            let resources = page_resources_mut(&mut doc, page_id)?;
+            if !resources.has(b"XObject") {
+                resources.set("XObject", Dictionary::new());
+            }
+            let xobjects = resources
+                .get_mut(b"XObject")?
+                .as_dict_mut()
+                .context("PDF page has an indirect image dictionary")?;
+            for index in names {
+                let id = image_ids
+                    .get(index)
+                    .with_context(|| format!("PDF page draws unknown image {}", index))?;
+                xobjects.set(image_name(index), Object::Reference(*id));
+            }
+        }
+
+        let mut bytes = Vec::new();
+        doc.save_to(&mut bytes)
+            .context("Failed to write PDF with card images")?;
+        Ok(bytes)
+    }
+}
+
+fn image_name(index: usize) -> String {
+    format!("{}{}", IMAGE_NAME_PREFIX, index)
+}
+
+/// Resources dictionary of the page, which printpdf writes as its own object
+pub(super) fn page_resources_mut(
+    doc: &mut lopdf::Document,
+    page_id: ObjectId,
+) -> Result<&mut Dictionary> {
+    let resources_id = match doc.get_dictionary(page_id)?.get(b"Resources") {
+        Ok(Object::Reference(id)) => *id,
+        _ => page_id,
+    };
+    let dict = doc.get_object_mut(resources_id)?.as_dict_mut()?;
+    if resources_id != page_id {
+        return Ok(dict);
+    }
+    if !dict.has(b"Resources") {
+        dict.set("Resources", Dictionary::new());
+    }
+    Ok(dict.get_mut(b"Resources")?.as_dict_mut()?)
+}
+
+#[cfg(test)]
+fn test_image() -> Arc<Image> {
+    let pixels = image::RgbImage::from_fn(20, 28, |x, y| image::Rgb([x as u8, y as u8, 90]));
+    Arc::new(Image::from_dynamic_image(&image::DynamicImage::ImageRgb8(
+        pixels,
+    )))
+}
+
+#[test]
+fn an_image_on_several_pages_is_embedded_once() {
+    use printpdf::PdfDocument;
+
+    let (doc, page, layer) = PdfDocument::new("Shared", Mm(210.0), Mm(297.0), "Cards");
+    let (second_page, second_layer) = doc.add_page(Mm(210.0), Mm(297.0), "Cards");
+    let layers = [
+        doc.get_page(page).get_layer(layer),
+        doc.get_page(second_page).get_layer(second_layer),
+    ];
+    let (island, forest) = (test_image(), test_image());
+    let images = SharedImages::default();
+    for layer in &layers {
+        images.place(layer, Arc::clone(&island), ImageTransform::default());
+        images.place(layer, Arc::clone(&island), ImageTransform::default());
+    }
+    images.place(&layers[1], forest, ImageTransform::default());
+
+    let pdf_bytes = images.embed(doc.save_to_bytes().unwrap()).unwrap();
+    let doc = lopdf::Document::load_mem(&pdf_bytes).unwrap();
+    let image_count = doc
+        .objects
+        .values()
+        .filter_map(|object| object.as_stream().ok())
+        .filter(|stream| {
+            stream.dict.get(b"Subtype").and_then(Object::as_name).ok() == Some(b"Image")
+        })
+        .count();
+    assert_eq!(image_count, 2);
+
+    let page_images: Vec<Vec<Vec<u8>>> = doc
+        .get_pages()
+        .into_values()
+        .map(|page_id| {
+            let (_, resource_ids) = doc.get_page_resources(page_id);
+            let resources = doc.get_dictionary(resource_ids[0]).unwrap();
+            let xobjects = resources.get(b"XObject").and_then(Object::as_dict).unwrap();
+            xobjects.iter().map(|(name, _)| name.clone()).collect()
+        })
+        .collect();
+    assert_eq!(
+        page_images,
+        [
+            vec![b"Card0".to_vec()],
+            vec![b"Card0".to_vec(), b"Card1".to_vec()]
+        ]
+    );
+}
+
+#[test]
+fn images_are_drawn_where_printpdf_would_draw_them() {
+    use printpdf::{ImageRotation, PdfDocument};
+
+    let transform = || ImageTransform {
+        translate_x: Some(Mm(20.0)),
+        translate_y: Some(Mm(30.0)),
+        rotate: Some(ImageRotation {
+            angle_ccw_degrees: 90.0,
+            ..Default::default()
+        }),
+        scale_x: Some(2.0),
+        scale_y: Some(1.5),
+        dpi: Some(600.0),
+    };
+    let (doc, page, layer) = PdfDocument::new("Shared", Mm(210.0), Mm(297.0), "Cards");
+    let layer = doc.get_page(page).get_layer(layer);
+    let image = test_image();
+    SharedImages::default().place(&layer, Arc::clone(&image), transform());
+    Image::from(image.image.clone()).add_to_layer(layer, transform());
+
+    let doc = lopdf::Document::load_mem(&doc.save_to_bytes().unwrap()).unwrap();
+    let page_id = *doc.get_pages().values().next().unwrap();
+    let matrices: Vec<Vec<f64>> = doc
+        .get_and_decode_page_content(page_id)
+        .unwrap()
+        .operations
+        .into_iter()
+        .filter(|operation| operation.operator == "cm")
+        .map(|operation| {
+            operation
+                .operands
+                .iter()
+                .map(|operand| operand.as_float().unwrap())
+                .collect()
+        })
+        .collect();
+    assert_eq!(matrices.len(), 2);
+    for (shared, printpdf) in matrices[0].iter().zip(&matrices[1]) {
+        assert!((shared - printpdf).abs() < 1e-3, "{:?}", matrices);
+    }
+}
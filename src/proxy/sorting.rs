@@ -0,0 +1,288 @@
+//! Output order of the cards. Cards are sorted within their decklist section, so the
+//! sections and their bookmarks stay in decklist order.
+
+use std::cmp::Ordering;
+
+use super::{Card, PendingCard};
+
+// WUBRG, the usual order of the colors on Magic cards
+const COLORS: [&str; 5] = ["W", "U", "B", "R", "G"];
+// Checked in this order, so an artifact creature counts as a creature
+const CARD_TYPES: [&str; 8] = [
+    "Creature",
+    "Planeswalker",
+    "Battle",
+    "Instant",
+    "Sorcery",
+    "Artifact",
+    "Enchantment",
+    "Land",
+];
+// Recognizes basic lands of local images, which have no type line
+const BASIC_LAND_NAMES: [&str; 6] = ["Plains", "Island", "Swamp", "Mountain", "Forest", "Wastes"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CardOrder {
+    #[default]
+    AsListed,
+    Alphabetical,
+    /// White, blue, black, red, green, then multicolored and colorless cards
+    ColorIdentity,
+    ManaValue,
+    /// Creatures first, lands last
+    Type,
+    /// By set, then collector number
+    Set,
+}
+
+impl CardOrder {
+    pub const ALL: [CardOrder; 6] = [
+        CardOrder::AsListed,
+        CardOrder::Alphabetical,
+        CardOrder::ColorIdentity,
+        CardOrder::ManaValue,
+        CardOrder::Type,
+        CardOrder::Set,
+    ];
+}
+
+impl std::fmt::Display for CardOrder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let label = match self {
+            CardOrder::AsListed => "As listed",
+            CardOrder::Alphabetical => "Alphabetical",
+            CardOrder::ColorIdentity => "By color identity",
+            CardOrder::ManaValue => "By mana value",
+            CardOrder::Type => "By card type",
+            CardOrder::Set => "Grouped by set",
+        };
+        f.write_str(label)
+    }
+}
+
+/// Sorts the cards within their decklist section. The sort is stable, cards that compare
+/// equal keep their decklist order.
+pub(super) fn sort_cards(cards: &mut [PendingCard], order: CardOrder, basic_lands_last: bool) {
+    let mut sections: Vec<Option<String>> = Vec::new();
+    for card in cards.iter() {
+        if !sections.contains(&card.label.section) {
+            sections.push(card.label.section.clone());
+        }
+    }
+    let section_index = |card: &PendingCard| sections.iter().position(|s| *s == card.label.section);
+    let goes_last = |card: &PendingCard| basic_lands_last && is_basic_land(card);
+
+    cards.sort_by(|a, b| {
+        section_index(a)
+            .cmp(&section_index(b))
+            .then_with(|| goes_last(a).cmp(&goes_last(b)))
+            .then_with(|| compare(a, b, order))
+    });
+}
+
+fn compare(a: &PendingCard, b: &PendingCard, order: CardOrder) -> Ordering {
+    match order {
+        CardOrder::AsListed => Ordering::Equal,
+        CardOrder::Alphabetical => by_name(a, b),
+        CardOrder::ColorIdentity => {
+            by_card_data(a, b, |a, b| color_rank(a).cmp(&color_rank(b))).then_with(|| by_name(a, b))
+        }
+        CardOrder::ManaValue => {
+            by_card_data(a, b, |a, b| a.cmc.total_cmp(&b.cmc)).then_with(|| by_name(a, b))
+        }
+        CardOrder::Type => by_card_data(a, b, |a, b| {
+            type_rank(a)
+                .cmp(&type_rank(b))
+                .then_with(|| a.cmc.total_cmp(&b.cmc))
+        })
+        .then_with(|| by_name(a, b)),
+        CardOrder::Set => set_key(a).cmp(&set_key(b)).then_with(|| by_name(a, b)),
+    }
+}
+
+fn by_name(a: &PendingCard, b: &PendingCard) -> Ordering {
+    a.label
+        .name
+        .to_lowercase()
+        .cmp(&b.label.name.to_lowercase())
+}
+
+// Local images without Scryfall data go after all other cards of their section
+fn by_card_data(
+    a: &PendingCard,
+    b: &PendingCard,
+    compare: impl Fn(&Card, &Card) -> Ordering,
+) -> Ordering {
+    match (&a.label.card, &b.label.card) {
+        (Some(a), Some(b)) => compare(a, b),
+        (Some(_), None) => Ordering::Less,
+        (None, Some(_)) => Ordering::Greater,
+        (None, None) => Ordering::Equal,
+    }
+}
+
+// Mono colored before multicolored cards, colorless cards last
+fn color_rank(card: &Card) -> (bool, usize, Vec<usize>) {
+    let mut colors: Vec<usize> = card
+        .color_identity
+        .iter()
+        .filter_map(|color| COLORS.iter().position(|c| c == color))
+        .collect();
+    colors.sort_unstable();
+    (colors.is_empty(), colors.len(), colors)
+}
+
+fn type_rank(card: &Card) -> usize {
+    let type_line = card.front_type_line();
+    CARD_TYPES
+        .iter()
+        .position(|card_type| type_line.contains(card_type))
+        .unwrap_or(CARD_TYPES.len())
+}
+
+// Collector numbers like "125" or "125a" are ordered by their number
+fn set_key(card: &PendingCard) -> (Option<String>, u32, String) {
+    let number = card.label.collector_number.clone().unwrap_or_default();
+    let digits: String = number.chars().take_while(char::is_ascii_digit).collect();
+    (
+        card.label.set.as_ref().map(|set| set.to_lowercase()),
+        digits.parse().unwrap_or(u32::MAX),
+        number,
+    )
+}
+
+fn is_basic_land(card: &PendingCard) -> bool {
+    match &card.label.card {
+        Some(card) => card.front_type_line().starts_with("Basic "),
+        None => BASIC_LAND_NAMES.contains(&card.label.name.as_str()),
+    }
+}
+
+#[cfg(test)]
+fn card(name: &str, section: &str, data: Option<(&[&str], f64, &str)>) -> PendingCard {
+    PendingCard {
+        label: super::CardLabel {
+            name: name.to_string(),
+            set: None,
+            collector_number: None,
+            section: Some(section.to_string()),
+            card: data.map(|(colors, cmc, type_line)| {
+                std::sync::Arc::new(Card {
+                    color_identity: colors.iter().map(|c| c.to_string()).collect(),
+                    cmc,
+                    type_line: Some(type_line.to_string()),
+                    ..Card::default()
+                })
+            }),
+        },
+        quantity: 1,
+        faces: Vec::new(),
+    }
+}
+
+#[cfg(test)]
+fn names(cards: &[PendingCard]) -> Vec<&str> {
+    cards.iter().map(|card| card.label.name.as_str()).collect()
+}
+
+#[test]
+fn cards_are_sorted_within_their_section() {
+    let mut cards = vec![
+        card("Shock", "Deck", None),
+        card("Counterspell", "Deck", None),
+        card("Negate", "Sideboard", None),
+        card("Abrade", "Sideboard", None),
+        card("Bolt", "Deck", None),
+    ];
+
+    sort_cards(&mut cards, CardOrder::Alphabetical, false);
+
+    assert_eq!(
+        names(&cards),
+        ["Bolt", "Counterspell", "Shock", "Abrade", "Negate"]
+    );
+}
+
+#[test]
+fn basic_lands_go_last_in_decklist_order() {
+    let mut cards = vec![
+        card(
+            "Snow-Covered Island",
+            "Deck",
+            Some((&["U"], 0.0, "Basic Snow Land — Island")),
+        ),
+        card("Mountain", "Deck", None),
+        card("Shock", "Deck", Some((&["R"], 1.0, "Instant"))),
+        card(
+            "Steam Vents",
+            "Deck",
+            Some((&["U", "R"], 0.0, "Land — Island Mountain")),
+        ),
+    ];
+
+    sort_cards(&mut cards, CardOrder::AsListed, true);
+
+    assert_eq!(
+        names(&cards),
+        ["Shock", "Steam Vents", "Snow-Covered Island", "Mountain"]
+    );
+}
+
+#[test]
+fn color_identity_order_is_wubrg_then_multicolored_then_colorless() {
+    let mut cards = vec![
+        card("Sol Ring", "Deck", Some((&[], 1.0, "Artifact"))),
+        card("Electrolyze", "Deck", Some((&["R", "U"], 3.0, "Instant"))),
+        card("Shock", "Deck", Some((&["R"], 1.0, "Instant"))),
+        card("Custom Alter", "Deck", None),
+        card(
+            "Swords to Plowshares",
+            "Deck",
+            Some((&["W"], 1.0, "Instant")),
+        ),
+        card("Counterspell", "Deck", Some((&["U"], 2.0, "Instant"))),
+    ];
+
+    sort_cards(&mut cards, CardOrder::ColorIdentity, false);
+
+    assert_eq!(
+        names(&cards),
+        [
+            "Swords to Plowshares",
+            "Counterspell",
+            "Shock",
+            "Electrolyze",
+            "Sol Ring",
+            "Custom Alter"
+        ]
+    );
+}
+
+#[test]
+fn type_order_uses_the_front_face() {
+    let mut cards = vec![
+        card("Island", "Deck", Some((&["U"], 0.0, "Basic Land — Island"))),
+        card("Opt", "Deck", Some((&["U"], 1.0, "Instant"))),
+        card(
+            "Delver of Secrets",
+            "Deck",
+            Some((
+                &["U"],
+                1.0,
+                "Creature — Human Wizard // Creature — Human Insect",
+            )),
+        ),
+        card(
+            "Ornithopter",
+            "Deck",
+            Some((&[], 0.0, "Artifact Creature — Thopter")),
+        ),
+    ];
+
+    sort_cards(&mut cards, CardOrder::Type, false);
+
+    assert_eq!(
+        names(&cards),
+        ["Ornithopter", "Delver of Secrets", "Opt", "Island"]
+    );
+}
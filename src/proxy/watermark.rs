@@ -0,0 +1,237 @@
+//! "PROXY" / "PLAYTEST" marking stamped onto every placed card.
+
+use anyhow::{Context, Result};
+use printpdf::lopdf::{self, content::Operation, Dictionary, Object};
+use printpdf::{Color, Greyscale, IndirectFontRef, Mm, PdfLayerReference, Pt, TextMatrix};
+
+use super::layout::Rect;
+use super::shared_images::page_resources_mut;
+
+// Approximate advance width of an upper case Helvetica Bold character, in em
+const CHAR_WIDTH_EM: f64 = 0.7;
+// Height of upper case letters, in em
+const CAP_HEIGHT_EM: f64 = 0.72;
+const MAX_FONT_SIZE_PT: f64 = 40.0;
+// Graphics state with the fill alpha of the watermark, in the resources of every page
+const OPACITY_STATE_NAME: &str = "WatermarkOpacity";
+
+/// Where on the card the watermark is centered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WatermarkPosition {
+    Top,
+    #[default]
+    Center,
+    Bottom,
+}
+
+impl WatermarkPosition {
+    pub const ALL: [WatermarkPosition; 3] = [
+        WatermarkPosition::Top,
+        WatermarkPosition::Center,
+        WatermarkPosition::Bottom,
+    ];
+}
+
+impl std::fmt::Display for WatermarkPosition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let label = match self {
+            WatermarkPosition::Top => "Top",
+            WatermarkPosition::Center => "Center",
+            WatermarkPosition::Bottom => "Bottom",
+        };
+        f.write_str(label)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Watermark {
+    pub text: String,
+    pub position: WatermarkPosition,
+    /// Alpha of the black text, 0.0 (invisible) to 1.0 (solid black)
+    pub opacity: f64,
+    /// Counter-clockwise, in degrees
+    pub rotation: f64,
+}
+
+impl Default for Watermark {
+    fn default() -> Self {
+        Watermark {
+            text: "PROXY".to_string(),
+            position: WatermarkPosition::Center,
+            opacity: 0.4,
+            rotation: 45.0,
+        }
+    }
+}
+
+impl Watermark {
+    /// Draws the text onto the card. The opacity is added to the saved PDF by
+    /// [`Watermark::add_opacity`].
+    pub(super) fn stamp(&self, layer: &PdfLayerReference, font: &IndirectFontRef, card: &Rect) {
+        let char_count = self.text.chars().count();
+        if char_count == 0 || self.opacity <= 0.0 {
+            return;
+        }
+
+        // Fit the text into the card width, measured along the text direction
+        let rotation = self.rotation.to_radians();
+        let available_mm = (card.width / rotation.cos().abs().max(0.01))
+            .min(card.height / rotation.sin().abs().max(0.01))
+            * 0.8;
+        let font_size = (Pt::from(Mm(available_mm)).0 / (char_count as f64 * CHAR_WIDTH_EM))
+            .min(MAX_FONT_SIZE_PT);
+
+        let center_x = card.x + card.width / 2.0;
+        let center_y = card.y
+            + match self.position {
+                WatermarkPosition::Top => card.height * 0.8,
+                WatermarkPosition::Center => card.height / 2.0,
+                WatermarkPosition::Bottom => card.height * 0.2,
+            };
+
+        // Move the text origin (start of the baseline) so the text is centered after rotating
+        let half_width = Pt(font_size * CHAR_WIDTH_EM * char_count as f64 / 2.0);
+        let half_height = Pt(font_size * CAP_HEIGHT_EM / 2.0);
+        let center = (Pt::from(Mm(center_x)), Pt::from(Mm(center_y)));
+        let origin_x = center.0 .0 - half_width.0 * rotation.cos() + half_height.0 * rotation.sin();
+        let origin_y = center.1 .0 - half_width.0 * rotation.sin() - half_height.0 * rotation.cos();
+
+        layer.save_graphics_state();
+        layer.add_operation(Operation::new(
+            "gs",
+            vec![Object::Name(OPACITY_STATE_NAME.as_bytes().to_vec())],
+        ));
+        layer.set_fill_color(Color::Greyscale(Greyscale::new(0.0, None)));
+        layer.begin_text_section();
+        layer.set_font(font, font_size);
+        layer.set_text_matrix(TextMatrix::TranslateRotate(
+            Pt(origin_x),
+            Pt(origin_y),
+            self.rotation,
+        ));
+        layer.write_text(self.text.clone(), font);
+        layer.end_text_section();
+        layer.restore_graphics_state();
+    }
+
+    /// Adds the graphics state with the opacity as fill alpha (`/ca`) to every page. printpdf
+    /// can't add graphics states to pages from outside and writes the fill alpha as `/CA`,
+    /// the stroke alpha, so it's added to the saved document with lopdf.
+    pub(super) fn add_opacity(&self, pdf_bytes: Vec<u8>) -> Result<Vec<u8>> {
+        let mut doc =
+            lopdf::Document::load_mem(&pdf_bytes).context("Failed to read created PDF")?;
+
+        let mut state = Dictionary::new();
+        state.set("Type", Object::Name(b"ExtGState".to_vec()));
+        state.set("ca", Object::Real(self.opacity.clamp(0.0, 1.0)));
+        let state_id = doc.add_object(state);
+
+        for page_id in doc.get_pages().into_values().collect::<Vec<_>>() {
+            let resources = page_resources_mut(&mut doc, page_id)?;
+            if !resources.has(b"ExtGState") {
+                resources.set("ExtGState", Dictionary::new());
+            }
+            resources
+                .get_mut(b"ExtGState")?
+                .as_dict_mut()
+                .context("PDF page has an indirect graphics state dictionary")?
+                .set(OPACITY_STATE_NAME, Object::Reference(state_id));
+        }
+
+        let mut bytes = Vec::new();
+        doc.save_to(&mut bytes)
+            .context("Failed to write PDF with watermark opacity")?;
+        Ok(bytes)
+    }
+}
+
+#[cfg(test)]
+fn stamped_page(watermark: &Watermark, card: &Rect) -> (lopdf::Document, Vec<Operation>) {
+    use printpdf::{BuiltinFont, PdfDocument};
+
+    let (doc, page, layer) = PdfDocument::new("Watermark", Mm(210.0), Mm(297.0), "Cards");
+    let font = doc.add_builtin_font(BuiltinFont::HelveticaBold).unwrap();
+    watermark.stamp(&doc.get_page(page).get_layer(layer), &font, card);
+    let pdf_bytes = watermark.add_opacity(doc.save_to_bytes().unwrap()).unwrap();
+
+    let doc = lopdf::Document::load_mem(&pdf_bytes).unwrap();
+    let page_id = doc.get_pages()[&1];
+    let operations = lopdf::content::Content::decode(&doc.get_page_content(page_id).unwrap())
+        .unwrap()
+        .operations;
+    (doc, operations)
+}
+
+#[test]
+fn the_opacity_is_the_fill_alpha_of_the_text() {
+    let watermark = Watermark {
+        opacity: 0.25,
+        ..Watermark::default()
+    };
+    let card = Rect {
+        x: 10.0,
+        y: 20.0,
+        width: 63.0,
+        height: 88.0,
+    };
+    let (doc, operations) = stamped_page(&watermark, &card);
+
+    let operators: Vec<_> = operations.iter().map(|op| op.operator.as_str()).collect();
+    let gs = operators.iter().position(|op| *op == "gs").unwrap();
+    let text = operators.iter().position(|op| *op == "Tj").unwrap();
+    assert!(gs < text);
+    assert_eq!(
+        operations[gs].operands[0].as_name().unwrap(),
+        OPACITY_STATE_NAME.as_bytes()
+    );
+    assert!(!operators.contains(&"BM"));
+
+    let page_id = doc.get_pages()[&1];
+    let resources = match doc.get_dictionary(page_id).unwrap().get(b"Resources") {
+        Ok(Object::Reference(id)) => doc.get_dictionary(*id).unwrap(),
+        resources => resources.unwrap().as_dict().unwrap(),
+    };
+    let states = resources.get(b"ExtGState").unwrap();
+    let state = states
+        .as_dict()
+        .unwrap()
+        .get(OPACITY_STATE_NAME.as_bytes())
+        .unwrap();
+    let state = doc.get_dictionary(state.as_reference().unwrap()).unwrap();
+    assert_eq!(state.get(b"ca").unwrap().as_float().unwrap(), 0.25);
+    assert!(state.get(b"CA").is_err());
+}
+
+#[test]
+fn the_text_is_centered_on_the_card() {
+    let watermark = Watermark {
+        rotation: 0.0,
+        ..Watermark::default()
+    };
+    let card = Rect {
+        x: 30.0,
+        y: 40.0,
+        width: 63.0,
+        height: 88.0,
+    };
+    let (_, operations) = stamped_page(&watermark, &card);
+
+    let operand = |operator: &str, index: usize| {
+        let operation = operations
+            .iter()
+            .find(|op| op.operator == operator)
+            .unwrap();
+        operation.operands[index].as_float().unwrap()
+    };
+    let font_size = operand("Tf", 1);
+    let (origin_x, origin_y) = (operand("Tm", 4), operand("Tm", 5));
+
+    let text_width = font_size * CHAR_WIDTH_EM * 5.0;
+    let center_x = Pt::from(Mm(card.x + card.width / 2.0)).0;
+    let center_y = Pt::from(Mm(card.y + card.height / 2.0)).0;
+    assert!((origin_x + text_width / 2.0 - center_x).abs() < 0.01);
+    assert!((origin_y + font_size * CAP_HEIGHT_EM / 2.0 - center_y).abs() < 0.01);
+    // at most 80 % of the card width, "PROXY" is limited by the largest font size
+    assert_eq!(font_size, MAX_FONT_SIZE_PT);
+    assert!(Mm::from(Pt(text_width)).0 <= card.width * 0.8);
+}
@@ -1,4 +1,4 @@
-use mtg_proxy_creator_rust::proxy::main;
+use mtg_proxy_creator_rust::proxy::{main, ProxyOptions};
 use std::fs;
 use std::{path::PathBuf, str::FromStr};
 
@@ -6,9 +6,12 @@ const TEST_TXT_FILE_PATH: &str = "input/test.txt";
 
 #[tokio::test]
 async fn test_run_function() {
-    let path = main(PathBuf::from_str(TEST_TXT_FILE_PATH).ok(), false, 0.0)
-        .await
-        .expect("main() failed");
+    let path = main(
+        PathBuf::from_str(TEST_TXT_FILE_PATH).ok(),
+        ProxyOptions::default(),
+    )
+    .await
+    .expect("main() failed");
 
     assert!(path.exists(), "PDF file does not exist");
 